@@ -2,7 +2,10 @@ use std::sync::{
 	Arc, Mutex,
 	atomic::{AtomicBool, Ordering},
 };
-use std::time::Duration;
+use std::{
+	thread,
+	time::{Duration, Instant},
+};
 
 use color_eyre::eyre;
 use rodio::{OutputStreamHandle, Sample, Source, cpal::FromSample};
@@ -30,6 +33,7 @@ impl AudioPlayer {
 		let controls = Arc::new(Controls {
 			pause: AtomicBool::new(false),
 			stopped: AtomicBool::new(false),
+			released: AtomicBool::new(false),
 			volume: Mutex::new(0.5),
 			speed: Mutex::new(1.0),
 			seek: Mutex::new(None),
@@ -145,6 +149,28 @@ impl AudioPlayer {
 		self.controls.stopped.store(true, Ordering::SeqCst);
 	}
 
+	/// Stops playback while `f` runs and resumes it from the same position afterwards, so that `f`
+	/// can write to the audio file without the decoder reading from it
+	pub fn with_released<T>(&self, f: impl FnOnce() -> T) -> eyre::Result<T> {
+		let was_playing = !self.controls.stopped.load(Ordering::SeqCst);
+		let position = self.position();
+		self.stop();
+
+		// the source notices it was stopped within a few milliseconds, unless no audio is output
+		let start = Instant::now();
+		while !self.controls.released.load(Ordering::SeqCst)
+			&& start.elapsed() < Duration::from_millis(500)
+		{
+			thread::sleep(Duration::from_millis(1));
+		}
+
+		let result = f();
+		if was_playing {
+			self.seek(position)?;
+		}
+		Ok(result)
+	}
+
 	/// Returns the position of the sound that's being played.
 	///
 	/// This takes into account any speedup or delay applied.
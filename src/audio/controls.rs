@@ -7,6 +7,8 @@ use std::{
 pub struct Controls {
 	pub pause: AtomicBool,
 	pub stopped: AtomicBool,
+	/// whether the source stopped reading the audio file after being stopped or reaching its end
+	pub released: AtomicBool,
 	pub volume: Mutex<f32>,
 	pub speed: Mutex<f32>,
 	pub seek: Mutex<Option<Duration>>,
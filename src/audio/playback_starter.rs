@@ -50,6 +50,7 @@ where
 	fn start_playback(&self) -> eyre::Result<()> {
 		let (source, duration) = (self.factory)()?;
 		*self.controls.duration.lock().unwrap() = duration;
+		self.controls.released.store(false, Ordering::SeqCst);
 
 		let access_controls = self.controls.clone();
 		let source = source
@@ -63,6 +64,7 @@ where
 					src.stop();
 					*access_controls.position.lock().unwrap() =
 						*access_controls.duration.lock().unwrap();
+					access_controls.released.store(true, Ordering::SeqCst);
 					return;
 				}
 				*access_controls.position.lock().unwrap() =
@@ -36,6 +36,7 @@ where
 		let next = self.input.next();
 		if !self.stop_set && next.is_none() {
 			self.controls.stopped.store(true, Ordering::Relaxed);
+			self.controls.released.store(true, Ordering::SeqCst);
 			self.stop_set = true;
 		}
 		next
@@ -17,6 +17,9 @@ pub struct Args {
 	/// print the location of the user config, then exit
 	#[arg(long, value_name = "FORMAT")]
 	pub print_config_path: Option<Option<ConfigFiletype>>,
+	/// convert a lyrics file to another format, detected from the file extensions, then exit
+	#[arg(long, num_args = 2, value_names = ["INPUT", "OUTPUT"])]
+	pub convert: Option<Vec<PathBuf>>,
 }
 
 #[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -8,6 +8,7 @@ use strum::{
 };
 
 use super::KeyChord;
+use crate::lyrics::editing::{Interpolation, SplitTimestamp};
 
 macro_rules! define_actions {// {{{
 	($($name:ident $({ $value:ident: $data:ty })?),+ $(,)?) => {
@@ -136,8 +137,34 @@ define_actions! {
 	Undo,
 	Redo,
 	SyncTimestamp,
+	SyncWordTimestamp,
 	AdjustTimestamp { centis: i32 },
+	AdjustOffset { millis: i32 },
+	BakeOffset,
+	ExpandRepeatedLines,
+	CompressRepeatedLines,
+	ExportLyrics { format: crate::lyrics::LyricsFormat },
+	ImportLyrics { format: crate::lyrics::LyricsFormat },
+	SetVoice { voice: Option<crate::lyrics::Voice> },
+	CycleVoice,
+	ViewProblems,
+	ViewHistory,
+	FixSafeProblems,
 	OpenInEditor,
+	EnterInsertMode,
+	DeleteCharacter { amount: i16 },
+	OpenLine { below: bool },
+	DeleteLine,
+	SplitLine { timestamp: crate::lyrics::editing::SplitTimestamp },
+	JoinLines,
+	MoveLine { amount: i16 },
+	EnterVisualMode,
+	ClearTimestamps,
+	CopyTimestamps,
+	PasteTimestamps,
+	SetStretchAnchor,
+	StretchTimestamps,
+	InterpolateTimestamps { interpolation: crate::lyrics::editing::Interpolation },
 	LeaveDirectory,
 	OpenFileOrDirectory,
 	ViewKeys,
@@ -183,7 +210,7 @@ impl fmt::Display for Action {
 			Action::SetCursorX { x: 0 } => f.write_str("Move cursor to line start"),
 			Action::SetCursorX { x: u16::MAX } => f.write_str("Move cursor to line end"),
 			Action::SetCursorX { x } => write!(f, "Move cursor to column {x}"),
-			Action::CursorToPlaying => f.write_str("Move cursor to currently playing line"),
+			Action::CursorToPlaying => f.write_str("Move cursor to currently playing word"),
 			Action::CursorToPlayingLine => f.write_str("Move cursor to currently playing line"),
 			Action::SeekRelative { progress } => write!(
 				f,
@@ -196,7 +223,7 @@ impl fmt::Display for Action {
 			Action::SeekForwards { seconds } => {
 				write!(f, "Jump playback forwards by {seconds} seconds")
 			}
-			Action::SeekToCursor => f.write_str("Jump playback to selected line"),
+			Action::SeekToCursor => f.write_str("Jump playback to selected word"),
 			Action::SeekToCursorLine => f.write_str("Jump playback to selected line"),
 			Action::TogglePause => f.write_str("Toggle playback paused"),
 			Action::ChangeVolume { percentage } => write!(f, "Change volume by {percentage:+}%"),
@@ -209,12 +236,89 @@ impl fmt::Display for Action {
 			Action::SyncTimestamp => f.write_str(
 				"Synchronise timestamp to current playback position and move cursor down",
 			),
+			Action::SyncWordTimestamp => f.write_str(
+				"Synchronise word timestamp to current playback position and move cursor to next word",
+			),
 			Action::AdjustTimestamp { centis } => write!(
 				f,
 				"Adjust selected timestamp by {:+} seconds",
 				(*centis as f32 / 100.)
 			),
+			Action::AdjustOffset { millis } => {
+				write!(f, "Adjust lyrics offset by {millis:+} milliseconds")
+			}
+			Action::BakeOffset => f.write_str("Apply lyrics offset to all timestamps"),
+			Action::ExpandRepeatedLines => {
+				f.write_str("Split lines with multiple timestamps into separate lines")
+			}
+			Action::CompressRepeatedLines => {
+				f.write_str("Merge repeated lines into one line with multiple timestamps")
+			}
+			Action::ExportLyrics { format } => {
+				write!(f, "Export lyrics to .{} file", format.extension())
+			}
+			Action::ImportLyrics { format } => {
+				write!(f, "Replace lyrics with .{} file", format.extension())
+			}
+			Action::SetVoice { voice: Some(voice) } => write!(f, "Set voice of line to {voice}"),
+			Action::SetVoice { voice: None } => f.write_str("Remove voice from line"),
+			Action::CycleVoice => f.write_str("Cycle voice of line"),
+			Action::ViewProblems => f.write_str("View problems in lyrics"),
+			Action::ViewHistory => f.write_str("View undo history and jump to any edit"),
+			Action::FixSafeProblems => f.write_str("Fix all problems which are safe to fix"),
 			Action::OpenInEditor => f.write_str("Open lyrics in external editor"),
+			Action::EnterInsertMode => f.write_str("Type at cursor"),
+			Action::DeleteCharacter { amount: 1 } => f.write_str("Delete character after cursor"),
+			Action::DeleteCharacter { amount: -1 } => f.write_str("Delete character before cursor"),
+			Action::DeleteCharacter {
+				amount: amount @ 1..,
+			} => write!(f, "Delete {amount} characters after cursor"),
+			Action::DeleteCharacter {
+				amount: amount @ ..0,
+			} => write!(f, "Delete {} characters before cursor", amount.abs()),
+			Action::DeleteCharacter { amount: 0 } => f.write_str("Delete nothing"),
+			Action::OpenLine { below: true } => f.write_str("Insert new line below and type in it"),
+			Action::OpenLine { below: false } => {
+				f.write_str("Insert new line above and type in it")
+			}
+			Action::DeleteLine => f.write_str("Delete line"),
+			Action::SplitLine { timestamp } => match timestamp {
+				SplitTimestamp::Word => f.write_str("Split line at cursor"),
+				SplitTimestamp::Playback => {
+					f.write_str("Split line at cursor, syncing the new line to current time")
+				}
+				SplitTimestamp::None => {
+					f.write_str("Split line at cursor, leaving the new line unsynced")
+				}
+			},
+			Action::JoinLines => f.write_str("Append next line to current"),
+			Action::MoveLine {
+				amount: amount @ 1..,
+			} => write!(f, "Move line down by {amount}"),
+			Action::MoveLine {
+				amount: amount @ ..0,
+			} => write!(f, "Move line up by {}", amount.abs()),
+			Action::MoveLine { amount: 0 } => f.write_str("Move line nowhere"),
+			Action::EnterVisualMode => f.write_str("Select lines"),
+			Action::ClearTimestamps => f.write_str("Remove timestamps of line or selection"),
+			Action::CopyTimestamps => f.write_str("Copy timestamps of line or selection"),
+			Action::PasteTimestamps => {
+				f.write_str("Paste copied timestamps onto line or selection and below")
+			}
+			Action::SetStretchAnchor => {
+				f.write_str("Mark line as anchor for time-stretch, at current playback position")
+			}
+			Action::StretchTimestamps => {
+				f.write_str("Time-stretch all timestamps or selection to fit the two anchors")
+			}
+			Action::InterpolateTimestamps { interpolation } => match interpolation {
+				Interpolation::Width => f.write_str(
+					"Fill in timestamps between synced lines of all lines or selection, by text width",
+				),
+				Interpolation::Even => f.write_str(
+					"Fill in timestamps between synced lines of all lines or selection, evenly",
+				),
+			},
 			Action::LeaveDirectory => f.write_str("Leave directory"),
 			Action::OpenFileOrDirectory => f.write_str("Open file or directory under cursor"),
 			Action::ViewKeys => f.write_str("Open this popup"),
@@ -267,11 +371,19 @@ where
 	}
 }
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct KeyMapContextConfig {
 	map: [Vec<ActionConfig>; ActionType::COUNT],
 }
 
+impl Default for KeyMapContextConfig {
+	fn default() -> Self {
+		Self {
+			map: std::array::from_fn(|_| Vec::new()),
+		}
+	}
+}
+
 impl KeyMapContextConfig {
 	pub fn new(key_map_context: &HashMap<KeyChord, Action>) -> Self {
 		let mut key_map_context_config = Self::default();
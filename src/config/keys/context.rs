@@ -11,5 +11,7 @@ pub enum Context {
 	ConfirmBox,
 	ScrollablePopup,
 	Editor,
+	/// typing in the editor, keys which are not bound here type text
+	Insert,
 	FileBrowser,
 }
@@ -5,6 +5,10 @@ use serde::{Deserialize, Serialize, de::Visitor, ser::SerializeMap};
 use strum::{EnumCount, IntoEnumIterator};
 
 use super::{Action, Context, KeyChord, action::KeyMapContextConfig};
+use crate::lyrics::{
+	LyricsFormat, Voice,
+	editing::{Interpolation, SplitTimestamp},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct KeyMap {
@@ -13,7 +17,7 @@ pub struct KeyMap {
 
 impl KeyMap {
 	pub fn get_action(&self, key_chord: KeyChord, context: Context) -> Option<Action> {
-		self.map[context as usize].get(&key_chord).map(|&k| k)
+		self.map[context as usize].get(&key_chord).copied()
 	}
 
 	pub fn iter(
@@ -170,6 +174,7 @@ impl Default for KeyMap {
 				Action::Redo,
 			),
 			(KeyChord::from_char(' '), Action::SyncTimestamp),
+			(KeyChord::from_char('m'), Action::SyncWordTimestamp),
 			(
 				KeyChord::from_char('s'),
 				Action::AdjustTimestamp { centis: 100 },
@@ -194,7 +199,190 @@ impl Default for KeyMap {
 				KeyChord::from_char('C'),
 				Action::AdjustTimestamp { centis: -1 },
 			),
+			(
+				KeyChord::from_char('>'),
+				Action::AdjustOffset { millis: 10 },
+			),
+			(
+				KeyChord::from_char('<'),
+				Action::AdjustOffset { millis: -10 },
+			),
+			(
+				KeyChord::new(KeyCode::Char('o'), KeyModifiers::ALT),
+				Action::BakeOffset,
+			),
+			(
+				KeyChord::new(KeyCode::Char('e'), KeyModifiers::ALT),
+				Action::ExpandRepeatedLines,
+			),
+			(
+				KeyChord::new(KeyCode::Char('c'), KeyModifiers::ALT),
+				Action::CompressRepeatedLines,
+			),
+			(
+				KeyChord::new(KeyCode::Char('s'), KeyModifiers::ALT),
+				Action::ExportLyrics {
+					format: LyricsFormat::Srt,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('S'), KeyModifiers::ALT),
+				Action::ImportLyrics {
+					format: LyricsFormat::Srt,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('v'), KeyModifiers::ALT),
+				Action::ExportLyrics {
+					format: LyricsFormat::Vtt,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('t'), KeyModifiers::ALT),
+				Action::ExportLyrics {
+					format: LyricsFormat::Ttml,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('T'), KeyModifiers::ALT),
+				Action::ImportLyrics {
+					format: LyricsFormat::Ttml,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('a'), KeyModifiers::ALT),
+				Action::ExportLyrics {
+					format: LyricsFormat::Ass,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('J'), KeyModifiers::ALT),
+				Action::ImportLyrics {
+					format: LyricsFormat::Json,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('p'), KeyModifiers::ALT),
+				Action::CycleVoice,
+			),
+			(
+				KeyChord::new(KeyCode::Char('1'), KeyModifiers::ALT),
+				Action::SetVoice {
+					voice: Some(Voice::Numbered(1)),
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('2'), KeyModifiers::ALT),
+				Action::SetVoice {
+					voice: Some(Voice::Numbered(2)),
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('0'), KeyModifiers::ALT),
+				Action::SetVoice { voice: None },
+			),
+			(KeyChord::from_char('!'), Action::ViewProblems),
+			(KeyChord::from_char('U'), Action::ViewHistory),
+			(
+				KeyChord::new(KeyCode::Char('!'), KeyModifiers::ALT),
+				Action::FixSafeProblems,
+			),
 			(KeyChord::from_char('I'), Action::OpenInEditor),
+			(KeyChord::from_char('i'), Action::EnterInsertMode),
+			(KeyChord::from_char('o'), Action::OpenLine { below: true }),
+			(KeyChord::from_char('O'), Action::OpenLine { below: false }),
+			(KeyChord::from_char('X'), Action::DeleteLine),
+			(
+				KeyChord::from_char('K'),
+				Action::SplitLine {
+					timestamp: SplitTimestamp::Word,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('K'), KeyModifiers::ALT),
+				Action::SplitLine {
+					timestamp: SplitTimestamp::Playback,
+				},
+			),
+			(KeyChord::from_char('J'), Action::JoinLines),
+			(
+				KeyChord::new(KeyCode::Char('j'), KeyModifiers::ALT),
+				Action::MoveLine { amount: 1 },
+			),
+			(
+				KeyChord::new(KeyCode::Char('k'), KeyModifiers::ALT),
+				Action::MoveLine { amount: -1 },
+			),
+			(KeyChord::from_char('V'), Action::EnterVisualMode),
+			(KeyChord::from_char('x'), Action::ClearTimestamps),
+			(KeyChord::from_char('y'), Action::CopyTimestamps),
+			(KeyChord::from_char('p'), Action::PasteTimestamps),
+			(
+				KeyChord::new(KeyCode::Char('a'), KeyModifiers::CONTROL),
+				Action::SetStretchAnchor,
+			),
+			(
+				KeyChord::new(KeyCode::Char('t'), KeyModifiers::CONTROL),
+				Action::StretchTimestamps,
+			),
+			(
+				KeyChord::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+				Action::InterpolateTimestamps {
+					interpolation: Interpolation::Width,
+				},
+			),
+			(
+				KeyChord::new(KeyCode::Char('e'), KeyModifiers::CONTROL),
+				Action::InterpolateTimestamps {
+					interpolation: Interpolation::Even,
+				},
+			),
+		]);
+		keymap.map[Context::Insert as usize] = HashMap::from([
+			(
+				KeyChord::new(KeyCode::Esc, KeyModifiers::NONE),
+				Action::Cancel,
+			),
+			(
+				KeyChord::new(KeyCode::Enter, KeyModifiers::NONE),
+				Action::Confirm,
+			),
+			(
+				KeyChord::new(KeyCode::Backspace, KeyModifiers::NONE),
+				Action::DeleteCharacter { amount: -1 },
+			),
+			(
+				KeyChord::new(KeyCode::Delete, KeyModifiers::NONE),
+				Action::DeleteCharacter { amount: 1 },
+			),
+			(
+				KeyChord::new(KeyCode::Down, KeyModifiers::NONE),
+				Action::MoveCursorY { amount: 1 },
+			),
+			(
+				KeyChord::new(KeyCode::Up, KeyModifiers::NONE),
+				Action::MoveCursorY { amount: -1 },
+			),
+			(
+				KeyChord::new(KeyCode::Left, KeyModifiers::NONE),
+				Action::MoveCursorX { amount: -1 },
+			),
+			(
+				KeyChord::new(KeyCode::Right, KeyModifiers::NONE),
+				Action::MoveCursorX { amount: 1 },
+			),
+			(
+				KeyChord::new(KeyCode::Home, KeyModifiers::NONE),
+				Action::SetCursorX { x: 0 },
+			),
+			(
+				KeyChord::new(KeyCode::End, KeyModifiers::NONE),
+				Action::SetCursorX { x: u16::MAX },
+			),
+			(
+				KeyChord::new(KeyCode::Char('w'), KeyModifiers::CONTROL),
+				Action::Save,
+			),
 		]);
 		keymap.map[Context::ConfirmBox as usize] = HashMap::from([
 			(KeyChord::from_char('y'), Action::Yes),
@@ -3,5 +3,5 @@ mod settings;
 mod theme;
 
 pub use keys::{Action, Context, KeyChord, KeyMap};
-pub use settings::Settings;
+pub use settings::{SaveTarget, Settings, TagPolicy};
 pub use theme::Theme;
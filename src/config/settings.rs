@@ -2,6 +2,106 @@ use serde::{Deserialize, Serialize};
 use serde_with::{DurationSecondsWithFrac, chrono::TimeDelta, serde_as};
 use std::{path::PathBuf, time::Duration};
 
+use crate::lyrics::{
+	DEFAULT_ASS_STYLES, FileEncoding, TagType, TimestampPrecision, WriteOptions,
+	lint::{self, TrackInfo},
+};
+
+/// Where lyrics are written to on save
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SaveTarget {
+	/// the lyrics file next to the audio file
+	#[default]
+	Sidecar,
+	/// the lyrics tag of the audio file
+	Embedded,
+	Both,
+}
+
+/// Which encoding lyrics files are written in on save
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SaveEncoding {
+	/// the encoding the file was read in
+	#[default]
+	Keep,
+	Utf8,
+}
+
+impl SaveEncoding {
+	/// The encoding to write a file in which was read in `encoding`
+	pub fn apply(self, encoding: FileEncoding) -> FileEncoding {
+		match self {
+			SaveEncoding::Keep => encoding,
+			SaveEncoding::Utf8 => encoding.to_utf8(),
+		}
+	}
+}
+
+/// When a header tag is filled in on save
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TagPolicy {
+	/// set or refresh the tag on every save
+	Always,
+	/// only add the tag if the lyrics do not have it yet
+	IfMissing,
+	#[default]
+	Never,
+}
+
+/// Which header tags are filled in on save, from the audio file or lrxed itself
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct AutoTags {
+	/// `[ti:]`, from the title tag of the audio file
+	pub title: TagPolicy,
+	/// `[ar:]`, from the artist tag of the audio file
+	pub artist: TagPolicy,
+	/// `[al:]`, from the album tag of the audio file
+	pub album: TagPolicy,
+	/// `[length:]`, from the duration of the audio file
+	pub length: TagPolicy,
+	/// `[re:]`, set to lrxed
+	pub tool: TagPolicy,
+	/// `[ve:]`, set to the version of lrxed
+	pub version: TagPolicy,
+	/// `[by:]`, from the `lyrics-by` setting
+	pub by: TagPolicy,
+}
+
+impl AutoTags {
+	/// The tags to fill in with their values, leaving out those which are unknown
+	pub fn values(&self, track: &TrackInfo, by: Option<&str>) -> Vec<(TagType, TagPolicy, String)> {
+		[
+			(TagType::Title, self.title, track.title.clone()),
+			(TagType::Artist, self.artist, track.artist.clone()),
+			(TagType::Album, self.album, track.album.clone()),
+			(
+				TagType::Length,
+				self.length,
+				track.duration.map(lint::format_length),
+			),
+			(TagType::Tool, self.tool, Some("lrxed".to_owned())),
+			(
+				TagType::Version,
+				self.version,
+				Some(env!("CARGO_PKG_VERSION").to_owned()),
+			),
+			(TagType::By, self.by, by.map(str::to_owned)),
+		]
+		.into_iter()
+		.filter(|(_, policy, _)| *policy != TagPolicy::Never)
+		.filter_map(|(tag, policy, value)| {
+			let value = value?.trim().to_owned();
+			(!value.is_empty()).then_some((tag, policy, value))
+		})
+		.collect()
+	}
+}
+
 #[serde_as]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -10,6 +110,18 @@ pub struct Settings {
 	pub scrolloff: u16,
 	pub default_path: Option<PathBuf>,
 	pub replace_txt_file_on_save: bool,
+	pub compress_repeated_lines_on_save: bool,
+	/// read a line directly following a line with the same timestamp as its translation
+	pub inline_translations: bool,
+	pub save_target: SaveTarget,
+	pub save_encoding: SaveEncoding,
+	pub auto_tags: AutoTags,
+	/// name written to the `[by:]` tag, see `auto-tags`
+	pub lyrics_by: Option<String>,
+	pub timestamp_precision: TimestampPrecision,
+	/// keep the undo history of each lyrics file after it is closed
+	pub persistent_history: bool,
+	pub ass_styles: String,
 	#[serde_as(as = "DurationSecondsWithFrac<f64>")]
 	pub notification_timeout: Duration,
 	#[serde_as(as = "DurationSecondsWithFrac<f64>")]
@@ -22,8 +134,27 @@ impl Default for Settings {
 			scrolloff: 8,
 			default_path: None,
 			replace_txt_file_on_save: false,
+			compress_repeated_lines_on_save: false,
+			inline_translations: false,
+			save_target: SaveTarget::default(),
+			save_encoding: SaveEncoding::default(),
+			auto_tags: AutoTags::default(),
+			lyrics_by: None,
+			timestamp_precision: TimestampPrecision::default(),
+			persistent_history: true,
+			ass_styles: DEFAULT_ASS_STYLES.to_owned(),
 			notification_timeout: Duration::from_secs(5),
 			sync_offset: TimeDelta::zero(),
 		}
 	}
 }
+
+impl Settings {
+	pub fn write_options(&self, duration: Option<Duration>) -> WriteOptions<'_> {
+		WriteOptions {
+			precision: self.timestamp_precision,
+			duration,
+			ass_styles: &self.ass_styles,
+		}
+	}
+}
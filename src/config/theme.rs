@@ -2,6 +2,8 @@ use ratatui::style::{Color, Modifier, Style, Stylize};
 use serde::{Deserialize, Serialize};
 use serde_with::{FromInto, serde_as, skip_serializing_none};
 
+use crate::lyrics::Voice;
+
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -26,9 +28,21 @@ pub struct Theme {
 	#[serde_as(as = "FromInto<StyleConfig>")]
 	pub lyrics_line: Style,
 	#[serde_as(as = "FromInto<StyleConfig>")]
+	pub selection: Style,
+	#[serde_as(as = "FromInto<StyleConfig>")]
 	pub text_secondary: Style,
 	#[serde_as(as = "FromInto<StyleConfig>")]
 	pub title: Style,
+	#[serde_as(as = "FromInto<StyleConfig>")]
+	pub voice_1: Style,
+	#[serde_as(as = "FromInto<StyleConfig>")]
+	pub voice_2: Style,
+	#[serde_as(as = "FromInto<StyleConfig>")]
+	pub voice_female: Style,
+	#[serde_as(as = "FromInto<StyleConfig>")]
+	pub voice_male: Style,
+	#[serde_as(as = "FromInto<StyleConfig>")]
+	pub voice_duet: Style,
 
 	#[serde_as(as = "FromInto<StyleConfig>")]
 	pub file_browser_parent_directory: Style,
@@ -54,8 +68,14 @@ impl Default for Theme {
 			cursorline: Style::new().on_black(),
 			inactive: Style::new().black(),
 			lyrics_line: Style::new().bold(),
+			selection: Style::new().on_dark_gray(),
 			text_secondary: Style::new().dark_gray(),
 			title: Style::new().magenta().bold(),
+			voice_1: Style::new().cyan(),
+			voice_2: Style::new().yellow(),
+			voice_female: Style::new().light_red(),
+			voice_male: Style::new().light_blue(),
+			voice_duet: Style::new().light_green(),
 
 			file_browser_parent_directory: Style::new().green(),
 			file_browser_directory: Style::new().blue(),
@@ -66,6 +86,20 @@ impl Default for Theme {
 	}
 }
 
+impl Theme {
+	/// Style of lines sung by `voice`, numbered voices alternate between the first two styles
+	pub fn voice(&self, voice: Option<Voice>) -> Style {
+		match voice {
+			None => Style::default(),
+			Some(Voice::Numbered(n)) if n % 2 == 1 => self.voice_1,
+			Some(Voice::Numbered(_)) => self.voice_2,
+			Some(Voice::Female) => self.voice_female,
+			Some(Voice::Male) => self.voice_male,
+			Some(Voice::Duet) => self.voice_duet,
+		}
+	}
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 struct StyleConfig {
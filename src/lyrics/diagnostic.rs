@@ -0,0 +1,48 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+	/// the line was read, but maybe not the way it was meant
+	Warning,
+	/// part of the line could not be read and was kept as text or an unknown tag
+	Error,
+}
+
+/// A malformed part of a lyrics file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+	/// line number in the file, starting at 1
+	pub line: usize,
+	/// character column in the line, starting at 1
+	pub column: usize,
+	pub message: String,
+	pub severity: Severity,
+}
+
+impl Diagnostic {
+	/// A diagnostic at byte index `idx` of `source`, which is line `line_idx` of the file
+	pub(super) fn new(
+		line_idx: usize,
+		source: &str,
+		idx: usize,
+		severity: Severity,
+		message: String,
+	) -> Self {
+		Self {
+			line: line_idx + 1,
+			column: source[..idx].chars().count() + 1,
+			message,
+			severity,
+		}
+	}
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Line {}, column {}: {}",
+			self.line, self.column, self.message
+		)
+	}
+}
@@ -1,10 +1,11 @@
 use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
 
 use crate::lyrics::{Lyrics, TimeIndex};
 
-use super::edit_action::EditAction;
+use super::{edit_action::EditAction, line_diff};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Edit {
 	forwards_action: EditAction,
 	backwards_action: EditAction,
@@ -18,6 +19,69 @@ impl Edit {
 		}
 	}
 
+	/// Combines edits into one which executes them in order, and undoes them in reverse order
+	pub fn compound(mut edits: Vec<Edit>) -> Self {
+		if edits.len() == 1 {
+			return edits.remove(0);
+		}
+		let (forwards, mut backwards): (Vec<_>, Vec<_>) = edits
+			.into_iter()
+			.map(|edit| (edit.forwards_action, edit.backwards_action))
+			.unzip();
+		backwards.reverse();
+		Self::new(
+			EditAction::Compound(forwards),
+			EditAction::Compound(backwards),
+		)
+	}
+
+	/// The edit which turns `old` into `new`, holding only the runs of lines which differ between
+	/// them, or `None` if they are the same
+	pub fn between(old: &Lyrics, new: &Lyrics) -> Option<Self> {
+		// the earlier runs are already replaced when a run is, so it starts at its new index
+		let mut edits: Vec<_> = line_diff::hunks(old.lines(), new.lines())
+			.into_iter()
+			.map(|hunk| {
+				Edit::new(
+					EditAction::SpliceLines {
+						idx: hunk.new.start as u16,
+						remove: hunk.old.len(),
+						lines: new.lines()[hunk.new.clone()].to_vec(),
+					},
+					EditAction::SpliceLines {
+						idx: hunk.new.start as u16,
+						remove: hunk.new.len(),
+						lines: old.lines()[hunk.old].to_vec(),
+					},
+				)
+			})
+			.collect();
+		if old.metadata() != new.metadata() || old.final_newline() != new.final_newline() {
+			edits.push(Edit::new(
+				EditAction::SetMetadata {
+					metadata: new.metadata().to_vec(),
+					final_newline: new.final_newline(),
+				},
+				EditAction::SetMetadata {
+					metadata: old.metadata().to_vec(),
+					final_newline: old.final_newline(),
+				},
+			));
+		}
+
+		(!edits.is_empty()).then(|| Self::compound(edits))
+	}
+
+	/// Makes this the edit from the state before it to the state after `next`, only valid if both
+	/// set the same part of the lyrics, like the text of the same line
+	pub fn merge(&mut self, next: Edit) {
+		self.forwards_action = next.forwards_action;
+	}
+
+	pub fn forwards_action(&self) -> &EditAction {
+		&self.forwards_action
+	}
+
 	pub fn execute_forwards(
 		&self,
 		lyrics: &mut Lyrics,
@@ -34,3 +98,60 @@ impl Edit {
 		self.backwards_action.execute(lyrics, time_index)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	fn assert_round_trip(old: &str, new: &str) {
+		let (old, new) = (parse(old), parse(new));
+		let edit = Edit::between(&old, &new).unwrap();
+
+		let mut lyrics = old.clone();
+		let mut time_index = TimeIndex::new(&lyrics);
+		edit.execute_forwards(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(lyrics.lines(), new.lines());
+		assert_eq!(lyrics.metadata(), new.metadata());
+		assert_eq!(lyrics.final_newline(), new.final_newline());
+		assert_eq!(time_index, TimeIndex::new(&new));
+
+		edit.execute_backwards(&mut lyrics, &mut time_index)
+			.unwrap();
+		assert_eq!(lyrics.lines(), old.lines());
+		assert_eq!(lyrics.metadata(), old.metadata());
+		assert_eq!(lyrics.final_newline(), old.final_newline());
+		assert_eq!(time_index, TimeIndex::new(&old));
+	}
+
+	#[test]
+	fn between_same_lyrics_is_none() {
+		let lyrics = parse("[ar:x]\n[00:01.00]a\n[00:02.00]b\n");
+		assert_eq!(Edit::between(&lyrics, &lyrics.clone()), None);
+	}
+
+	#[test]
+	fn between_round_trips() {
+		let old = "[ar:x]\n[00:01.00]a\n[00:02.00]b\n[00:03.00]c\n[00:04.00]d\n";
+		assert_round_trip(
+			old,
+			"[ar:x]\n[00:01.00]a\n[00:02.50]new\n[00:03.00]c\n[00:04.00]d\n",
+		);
+		assert_round_trip(
+			old,
+			"[ar:x]\n[00:00.50]first\n[00:01.00]a\n[00:02.00]b\n[00:03.00]c\n[00:04.00]d\n[00:05.00]e\n",
+		);
+		assert_round_trip(old, "[ar:x]\n[00:02.00]b\n[00:04.00]d\n");
+		assert_round_trip(
+			old,
+			"[ar:y]\n[00:01.00]a\n[00:02.00]b\n[00:03.00]c\n[00:04.00]d",
+		);
+		assert_round_trip(old, "[00:04.00]d\n[00:03.00]c\n[00:02.00]b\n[00:01.00]a\n");
+		assert_round_trip(old, "");
+	}
+}
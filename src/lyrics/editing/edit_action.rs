@@ -1,16 +1,79 @@
 use std::time::Duration;
 
 use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
 
-use crate::lyrics::{Lyrics, TimeIndex};
+use crate::lyrics::{LyricLine, Lyrics, Metadata, TimeIndex, Timestamp, Voice};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EditAction {
 	SetTimestamp {
 		idx: u16,
 		timestamp: Option<Duration>,
 	},
-	RestoreState(Vec<u8>),
+	SetWordTimestamp {
+		idx: u16,
+		word: usize,
+		timestamp: Option<Duration>,
+	},
+	SetOffset(Option<i64>),
+	SetVoice {
+		idx: u16,
+		voice: Option<Voice>,
+	},
+	/// Sets the text of a line along with its word timestamps, whose byte indices depend on it
+	SetText {
+		idx: u16,
+		text: String,
+		word_timestamps: Vec<(usize, Timestamp)>,
+	},
+	/// Inserts a line at `idx`, after the first `tags_before` of the tags before the line which
+	/// was at `idx`
+	InsertLine {
+		idx: u16,
+		line: Box<LyricLine>,
+		tags_before: usize,
+	},
+	RemoveLine {
+		idx: u16,
+	},
+	/// Cuts line `idx` at byte `at` and inserts the rest after the `skip` bytes following it as
+	/// the text of `next`
+	SplitLine {
+		idx: u16,
+		at: usize,
+		skip: usize,
+		next: Box<LyricLine>,
+		tags_before: usize,
+	},
+	/// Appends line `idx + 1` to line `idx` after `separator`, which fails if it has translations
+	/// or another voice than line `idx`
+	JoinLines {
+		idx: u16,
+		separator: String,
+	},
+	MoveLine {
+		from: u16,
+		to: u16,
+	},
+	/// Replaces as many lines as given, starting at `idx`
+	SetLines {
+		idx: u16,
+		lines: Vec<LyricLine>,
+	},
+	/// Replaces the `remove` lines starting at `idx` with `lines`, leaving the tags where they are
+	SpliceLines {
+		idx: u16,
+		remove: usize,
+		lines: Vec<LyricLine>,
+	},
+	/// Replaces all tags, and whether the file ends with a newline
+	SetMetadata {
+		metadata: Vec<Metadata>,
+		final_newline: bool,
+	},
+	/// Executes the actions in order, or none of them if one fails
+	Compound(Vec<EditAction>),
 }
 
 impl EditAction {
@@ -22,13 +85,231 @@ impl EditAction {
 				}
 
 				lyrics.set_timestamp_at_line(*idx as usize, *timestamp);
-				time_index.rebuild(lyrics.lines().iter());
+				time_index.update_lines(lyrics, *idx as usize, 1, 1);
 			}
-			EditAction::RestoreState(buffer) => {
-				lyrics.read_overwrite(&buffer[..])?;
-				time_index.rebuild(lyrics.lines().iter());
+			EditAction::SetWordTimestamp {
+				idx,
+				word,
+				timestamp,
+			} => {
+				if lyrics.line_count() <= *idx {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+				if !lyrics.lines()[*idx as usize].text().is_char_boundary(*word) {
+					return Err(eyre::eyre!("Word index out of range"));
+				}
+
+				lyrics.set_word_timestamp_at_line(*idx as usize, *word, *timestamp);
+				time_index.update_lines(lyrics, *idx as usize, 1, 1);
+			}
+			EditAction::SetOffset(offset) => {
+				lyrics.set_offset(*offset);
+				time_index.rebuild(lyrics);
+			}
+			EditAction::SetVoice { idx, voice } => {
+				if lyrics.line_count() <= *idx {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+
+				lyrics.set_voice_at_line(*idx as usize, *voice);
+			}
+			EditAction::SetText {
+				idx,
+				text,
+				word_timestamps,
+			} => {
+				if lyrics.line_count() <= *idx {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+				if !LyricLine::word_timestamps_fit(text, word_timestamps) {
+					return Err(eyre::eyre!("Word index out of range"));
+				}
+
+				lyrics.set_text_at_line(*idx as usize, text.clone(), word_timestamps.clone());
+				time_index.update_lines(lyrics, *idx as usize, 1, 1);
+			}
+			EditAction::InsertLine {
+				idx,
+				line,
+				tags_before,
+			} => {
+				if lyrics.line_count() < *idx {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+				if !line.is_valid() {
+					return Err(eyre::eyre!("Word index out of range"));
+				}
+
+				lyrics.insert_line(*idx as usize, (**line).clone(), *tags_before);
+				time_index.update_lines(lyrics, *idx as usize, 0, 1);
+			}
+			EditAction::RemoveLine { idx } => {
+				if lyrics.line_count() <= *idx {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+
+				let remaining = lyrics.line_count() - 1;
+				lyrics.remove_line(*idx as usize);
+				// the last remaining line is replaced with an empty one
+				let inserted = (lyrics.line_count() - remaining) as usize;
+				time_index.update_lines(lyrics, *idx as usize, 1, inserted);
+			}
+			EditAction::SplitLine {
+				idx,
+				at,
+				skip,
+				next,
+				tags_before,
+			} => {
+				if lyrics.line_count() <= *idx {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+				if lyrics.lines()[*idx as usize]
+					.text()
+					.get(*at..at.saturating_add(*skip))
+					.is_none()
+				{
+					return Err(eyre::eyre!("Text index out of range"));
+				}
+				if !next.is_valid() {
+					return Err(eyre::eyre!("Word index out of range"));
+				}
+
+				lyrics.split_line(*idx as usize, *at, *skip, (**next).clone(), *tags_before);
+				time_index.update_lines(lyrics, *idx as usize, 1, 2);
+			}
+			EditAction::JoinLines { idx, separator } => {
+				if lyrics.line_count() <= *idx + 1 {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+				let (line, next) = (
+					&lyrics.lines()[*idx as usize],
+					&lyrics.lines()[*idx as usize + 1],
+				);
+				if !next.secondary_lines().is_empty() {
+					return Err(eyre::eyre!(
+						"The next line has translations, join them by hand"
+					));
+				}
+				if next.voice() != line.voice() {
+					return Err(eyre::eyre!("The next line is sung by another voice"));
+				}
+
+				lyrics.join_lines(*idx as usize, separator);
+				time_index.update_lines(lyrics, *idx as usize, 2, 1);
+			}
+			EditAction::MoveLine { from, to } => {
+				if lyrics.line_count() <= *from.max(to) {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+
+				lyrics.move_line(*from as usize, *to as usize);
+				let moved = (*from).min(*to) as usize..(*from).max(*to) as usize + 1;
+				time_index.update_lines(lyrics, moved.start, moved.len(), moved.len());
+			}
+			EditAction::SetLines { idx, lines } => {
+				if (lyrics.line_count() as usize) < *idx as usize + lines.len() {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+				if !lines.iter().all(LyricLine::is_valid) {
+					return Err(eyre::eyre!("Word index out of range"));
+				}
+
+				lyrics.replace_lines(*idx as usize, lines.clone());
+				time_index.update_lines(lyrics, *idx as usize, lines.len(), lines.len());
+			}
+			EditAction::SpliceLines { idx, remove, lines } => {
+				if (lyrics.line_count() as usize) < (*idx as usize).saturating_add(*remove) {
+					return Err(eyre::eyre!("Line index out of range"));
+				}
+				if !lines.iter().all(LyricLine::is_valid) {
+					return Err(eyre::eyre!("Word index out of range"));
+				}
+
+				let remaining = lyrics.line_count() as usize - remove;
+				lyrics.splice_lines(*idx as usize, *remove, lines.clone());
+				let inserted = lyrics.line_count() as usize - remaining;
+				time_index.update_lines(lyrics, *idx as usize, *remove, inserted);
+			}
+			EditAction::SetMetadata {
+				metadata,
+				final_newline,
+			} => {
+				let offset = lyrics.offset();
+				lyrics.set_metadata(metadata.clone(), *final_newline);
+				if lyrics.offset() != offset {
+					time_index.rebuild(lyrics);
+				}
+			}
+			EditAction::Compound(actions) => {
+				// an action failing halfway leaves the lyrics as they were, like the others do
+				let before = lyrics.clone();
+				if let Err(e) = actions
+					.iter()
+					.try_for_each(|action| action.execute(lyrics, time_index))
+				{
+					*lyrics = before;
+					time_index.rebuild(lyrics);
+					return Err(e);
+				}
 			}
 		};
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	#[test]
+	fn failing_compound_changes_nothing() {
+		let mut lyrics = parse("[00:01.00]one\n[00:02.00]two\n[00:03.00]three\n");
+		let before = lyrics.clone();
+		let mut time_index = TimeIndex::new(&lyrics);
+		let action = EditAction::Compound(vec![
+			EditAction::SetTimestamp {
+				idx: 0,
+				timestamp: Some(Duration::from_secs(5)),
+			},
+			EditAction::RemoveLine { idx: 1 },
+			EditAction::MoveLine { from: 0, to: 5 },
+		]);
+
+		assert!(action.execute(&mut lyrics, &mut time_index).is_err());
+		assert_eq!(lyrics, before);
+		assert_eq!(time_index, TimeIndex::new(&before));
+	}
+
+	#[test]
+	fn rejects_word_timestamps_which_do_not_fit_the_text() {
+		let mut lyrics = parse("[00:01.00]héllo\n");
+		let mut time_index = TimeIndex::new(&lyrics);
+		let timestamp: Timestamp = "00:01.50".parse().unwrap();
+		for word_timestamps in [
+			vec![(9, timestamp.clone())],
+			vec![(2, timestamp.clone())],
+			vec![(3, timestamp.clone()), (0, timestamp.clone())],
+		] {
+			let action = EditAction::SetText {
+				idx: 0,
+				text: "héllo".to_owned(),
+				word_timestamps,
+			};
+			assert!(action.execute(&mut lyrics, &mut time_index).is_err());
+		}
+		let action = EditAction::SetWordTimestamp {
+			idx: 0,
+			word: 2,
+			timestamp: Some(Duration::from_secs(2)),
+		};
+		assert!(action.execute(&mut lyrics, &mut time_index).is_err());
+		assert!(lyrics.lines()[0].word_timestamps().is_empty());
+	}
+}
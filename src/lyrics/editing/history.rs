@@ -1,34 +1,515 @@
+use std::{
+	borrow::Cow,
+	fs,
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
+
 use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
 
-use crate::lyrics::{Lyrics, TimeIndex};
+use crate::lyrics::{Lyrics, TimeIndex, TimestampPrecision};
 
 use super::Edit;
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// An edit in the undo tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+	edit: Edit,
+	/// the entry this edit was made after, `None` for the state the lyrics were opened in
+	parent: Option<usize>,
+	description: String,
+	time: SystemTime,
+	/// when the entry was last made or redone, redo follows the most recently visited child
+	visited: u64,
+}
+
+impl HistoryEntry {
+	pub fn parent(&self) -> Option<usize> {
+		self.parent
+	}
+
+	pub fn description(&self) -> &str {
+		&self.description
+	}
+
+	pub fn time(&self) -> SystemTime {
+		self.time
+	}
+}
+
+/// Undo tree like vim's, edits made after undoing start a new branch instead of replacing the
+/// edits which were undone
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct History {
-	undo_stack: Vec<Edit>,
-	redo_stack: Vec<Edit>,
+	/// every edit in the order it was made
+	entries: Vec<HistoryEntry>,
+	/// the entry the lyrics are at, `None` for the state they were opened in
+	current: Option<usize>,
+	visits: u64,
 }
 
 impl History {
-	pub fn push(&mut self, edit: Edit) {
-		self.undo_stack.push(edit);
-		self.redo_stack.clear();
+	pub fn push(&mut self, edit: Edit, description: String) {
+		self.visits += 1;
+		self.entries.push(HistoryEntry {
+			edit,
+			parent: self.current,
+			description,
+			time: SystemTime::now(),
+			visited: self.visits,
+		});
+		self.current = Some(self.entries.len() - 1);
+	}
+
+	/// Pushes an edit which is undone and redone together with the last one, see [`Edit::merge`].
+	/// Once another branch was made from the last edit, the edit is pushed on its own.
+	pub fn push_merged(&mut self, edit: Edit, description: String) {
+		match self.current {
+			Some(current)
+				if !self
+					.entries
+					.iter()
+					.any(|entry| entry.parent == Some(current)) =>
+			{
+				let entry = &mut self.entries[current];
+				entry.edit.merge(edit);
+				entry.description = description;
+				entry.time = SystemTime::now();
+			}
+			_ => self.push(edit, description),
+		}
+	}
+
+	/// The edit which is undone next
+	pub fn last(&self) -> Option<&Edit> {
+		self.current.map(|current| &self.entries[current].edit)
+	}
+
+	pub fn entries(&self) -> &[HistoryEntry] {
+		&self.entries
+	}
+
+	/// The entry the lyrics are at, `None` for the state they were opened in
+	pub fn current(&self) -> Option<usize> {
+		self.current
+	}
+
+	/// Whether the lyrics are at `entry` or an entry made after it on the same branch
+	pub fn is_reached(&self, entry: Option<usize>) -> bool {
+		entry.is_none() || self.ancestors(self.current).contains(&entry)
 	}
 
 	pub fn undo(&mut self, lyrics: &mut Lyrics, time_index: &mut TimeIndex) -> eyre::Result<()> {
-		if let Some(edit) = self.undo_stack.pop() {
-			edit.execute_backwards(lyrics, time_index)?;
-			self.redo_stack.push(edit);
+		if let Some(current) = self.current {
+			let entry = &self.entries[current];
+			entry.edit.execute_backwards(lyrics, time_index)?;
+			self.current = entry.parent;
 		}
 		Ok(())
 	}
 
 	pub fn redo(&mut self, lyrics: &mut Lyrics, time_index: &mut TimeIndex) -> eyre::Result<()> {
-		if let Some(edit) = self.redo_stack.pop() {
-			edit.execute_forwards(lyrics, time_index)?;
-			self.undo_stack.push(edit);
+		let next = (0..self.entries.len())
+			.filter(|&idx| self.entries[idx].parent == self.current)
+			.max_by_key(|&idx| self.entries[idx].visited);
+		if let Some(next) = next {
+			self.redo_entry(next, lyrics, time_index)?;
+		}
+		Ok(())
+	}
+
+	/// Undoes and redoes edits until the lyrics are at `target`, which may be on another branch
+	pub fn jump_to(
+		&mut self,
+		target: Option<usize>,
+		lyrics: &mut Lyrics,
+		time_index: &mut TimeIndex,
+	) -> eyre::Result<()> {
+		if target.is_some_and(|target| self.entries.len() <= target) {
+			return Err(eyre::eyre!("No such edit"));
+		}
+		let path = self.ancestors(target);
+		while !path.contains(&self.current) {
+			self.undo(lyrics, time_index)?;
+		}
+		let redone = path.iter().position(|&entry| entry == self.current);
+		for entry in path[..redone.unwrap_or_default()].iter().rev().flatten() {
+			self.redo_entry(*entry, lyrics, time_index)?;
 		}
 		Ok(())
 	}
+
+	fn redo_entry(
+		&mut self,
+		entry: usize,
+		lyrics: &mut Lyrics,
+		time_index: &mut TimeIndex,
+	) -> eyre::Result<()> {
+		self.entries[entry]
+			.edit
+			.execute_forwards(lyrics, time_index)?;
+		self.visits += 1;
+		self.entries[entry].visited = self.visits;
+		self.current = Some(entry);
+		Ok(())
+	}
+
+	/// `entry`, the entries before it on its branch and lastly `None`
+	fn ancestors(&self, entry: Option<usize>) -> Vec<Option<usize>> {
+		let mut ancestors = vec![entry];
+		let mut entry = entry;
+		while let Some(idx) = entry {
+			entry = self.entries[idx].parent;
+			ancestors.push(entry);
+		}
+		ancestors
+	}
+
+	/// Saves the history to a file in `dir` named after `lyrics_file`, from which it is loaded
+	/// again as long as the lyrics read from the file are the `lyrics` it was saved with, as they
+	/// are written with `precision`
+	pub fn save(
+		&self,
+		dir: &Path,
+		lyrics_file: &Path,
+		lyrics: &Lyrics,
+		precision: TimestampPrecision,
+	) -> eyre::Result<()> {
+		let lyrics_file = lyrics_file.canonicalize()?;
+		let saved = SavedHistory {
+			content_hash: lyrics_hash(lyrics, precision)?,
+			precision,
+			lyrics_file,
+			history: Cow::Borrowed(self),
+		};
+		fs::create_dir_all(dir)?;
+		fs::write(
+			Self::file_path(dir, &saved.lyrics_file),
+			serde_json::to_vec(&saved)?,
+		)?;
+		Ok(())
+	}
+
+	/// Loads the history saved for `lyrics_file`, if there is one and `lyrics`, as read from the
+	/// file, are the lyrics it was saved with. A history which does not fit them is dropped.
+	pub fn load(dir: &Path, lyrics_file: &Path, lyrics: &Lyrics) -> Option<Self> {
+		let lyrics_file = lyrics_file.canonicalize().ok()?;
+		let saved: SavedHistory =
+			serde_json::from_slice(&fs::read(Self::file_path(dir, &lyrics_file)).ok()?).ok()?;
+		(saved.lyrics_file == lyrics_file
+			&& saved.content_hash == lyrics_hash(lyrics, saved.precision).ok()?
+			&& saved.history.is_valid()
+			&& saved.history.replays(lyrics))
+		.then(|| saved.history.into_owned())
+	}
+
+	/// Whether every entry points at an entry made before it, which rules out cycles, and the
+	/// current entry exists
+	fn is_valid(&self) -> bool {
+		self.current
+			.is_none_or(|current| current < self.entries.len())
+			&& self
+				.entries
+				.iter()
+				.enumerate()
+				.all(|(idx, entry)| entry.parent.is_none_or(|parent| parent < idx))
+	}
+
+	/// Whether every entry can be reached from `lyrics`, which the history is at, without an edit
+	/// failing, so that every index in the edits fits the lyrics the edit applies to
+	fn replays(&self, lyrics: &Lyrics) -> bool {
+		let mut history = self.clone();
+		let mut lyrics = lyrics.clone();
+		let mut time_index = TimeIndex::new(&lyrics);
+		[None]
+			.into_iter()
+			.chain((0..self.entries.len()).map(Some))
+			.chain([self.current])
+			.all(|entry| history.jump_to(entry, &mut lyrics, &mut time_index).is_ok())
+	}
+
+	fn file_path(dir: &Path, lyrics_file: &Path) -> PathBuf {
+		dir.join(format!(
+			"{:016x}.json",
+			hash(lyrics_file.as_os_str().as_encoded_bytes())
+		))
+	}
+}
+
+/// The history of a lyrics file along with the lyrics it was saved with
+#[derive(Serialize, Deserialize)]
+struct SavedHistory<'a> {
+	lyrics_file: PathBuf,
+	/// hash of the lyrics, and of the lines of each translation, as written with `precision`
+	content_hash: u64,
+	precision: TimestampPrecision,
+	history: Cow<'a, History>,
+}
+
+fn lyrics_hash(lyrics: &Lyrics, precision: TimestampPrecision) -> eyre::Result<u64> {
+	let mut bytes = Vec::new();
+	lyrics.write_to(&mut bytes, precision)?;
+	for lang in lyrics.secondary_langs() {
+		bytes.extend_from_slice(lang.as_bytes());
+		Lyrics::from_lines(lyrics.secondary_lines(lang)).write_to(&mut bytes, precision)?;
+	}
+	Ok(hash(&bytes))
+}
+
+/// FNV-1a, which unlike the std hasher gives the same hash in every build
+fn hash(bytes: &[u8]) -> u64 {
+	bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+		(hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::{Value, json};
+
+	use super::*;
+	use crate::lyrics::{LyricLine, editing::EditAction};
+
+	fn parse(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("lrxed-{}-{name}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	/// The edit which sets the text of line `idx`, executed on `lyrics`
+	fn text_edit(lyrics: &mut Lyrics, time_index: &mut TimeIndex, idx: u16, text: &str) -> Edit {
+		let line = &lyrics.lines()[idx as usize];
+		let edit = Edit::new(
+			EditAction::SetText {
+				idx,
+				text: text.to_owned(),
+				word_timestamps: Vec::new(),
+			},
+			EditAction::SetText {
+				idx,
+				text: line.text().to_owned(),
+				word_timestamps: line.word_timestamps().to_vec(),
+			},
+		);
+		edit.execute_forwards(lyrics, time_index).unwrap();
+		edit
+	}
+
+	/// Sets the text of line `idx` and pushes the edit to `history`
+	fn set_text(
+		history: &mut History,
+		lyrics: &mut Lyrics,
+		time_index: &mut TimeIndex,
+		idx: u16,
+		text: &str,
+	) {
+		let edit = text_edit(lyrics, time_index, idx, text);
+		history.push(edit, format!("set text of line {}", idx + 1));
+	}
+
+	fn texts(lyrics: &Lyrics) -> Vec<&str> {
+		lyrics.lines().iter().map(LyricLine::text).collect()
+	}
+
+	/// A history with two branches, saved along with the lyrics it is at, as the editor saves a
+	/// song, returns the directory the history is kept in and the lyrics file
+	fn saved_history(name: &str) -> (History, PathBuf, PathBuf) {
+		let dir = temp_dir(name);
+		let lyrics_file = dir.join("song.lrc");
+		let mut lyrics = parse("[ar:x]\n[00:01.00]<00:01.00>one <00:01.50>two\n[00:02.00]three\n");
+		let mut time_index = TimeIndex::new(&lyrics);
+		let mut history = History::default();
+		set_text(&mut history, &mut lyrics, &mut time_index, 0, "uno");
+		set_text(&mut history, &mut lyrics, &mut time_index, 1, "tres");
+		history.undo(&mut lyrics, &mut time_index).unwrap();
+		set_text(&mut history, &mut lyrics, &mut time_index, 1, "drei");
+
+		let mut bytes = Vec::new();
+		lyrics
+			.write_to(&mut bytes, TimestampPrecision::Keep)
+			.unwrap();
+		fs::write(&lyrics_file, bytes).unwrap();
+		history
+			.save(
+				&dir.join("history"),
+				&lyrics_file,
+				&lyrics,
+				TimestampPrecision::Keep,
+			)
+			.unwrap();
+		(history, dir, lyrics_file)
+	}
+
+	fn load(dir: &Path, lyrics_file: &Path) -> Option<History> {
+		History::load(
+			&dir.join("history"),
+			lyrics_file,
+			&parse(&fs::read_to_string(lyrics_file).unwrap()),
+		)
+	}
+
+	/// Changes the saved history file with `f`
+	fn tamper(dir: &Path, lyrics_file: &Path, f: impl FnOnce(&mut Value)) {
+		let path = History::file_path(&dir.join("history"), &lyrics_file.canonicalize().unwrap());
+		let mut saved: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+		f(&mut saved["history"]);
+		fs::write(&path, serde_json::to_vec(&saved).unwrap()).unwrap();
+	}
+
+	#[test]
+	fn save_and_load_round_trip() {
+		let (history, dir, lyrics_file) = saved_history("round-trip");
+		let loaded = load(&dir, &lyrics_file).unwrap();
+		assert_eq!(loaded, history);
+
+		let mut lyrics = parse(&fs::read_to_string(&lyrics_file).unwrap());
+		let mut time_index = TimeIndex::new(&lyrics);
+		let mut loaded = loaded;
+		loaded
+			.jump_to(Some(1), &mut lyrics, &mut time_index)
+			.unwrap();
+		assert_eq!(texts(&lyrics), ["uno", "tres"]);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn load_drops_history_of_other_lyrics() {
+		let (_, dir, lyrics_file) = saved_history("other-lyrics");
+		fs::write(&lyrics_file, "[00:01.00]edited elsewhere\n").unwrap();
+		assert_eq!(load(&dir, &lyrics_file), None);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	fn words(history: &mut Value) -> &mut Value {
+		&mut history["entries"][0]["edit"]["backwards_action"]["SetText"]["word_timestamps"]
+	}
+
+	/// Saves a history, changes the saved file with `corrupt` and checks that it is not loaded
+	fn assert_dropped(name: &str, corrupt: impl FnOnce(&mut Value)) {
+		let (_, dir, lyrics_file) = saved_history(&format!("corrupt-{name}"));
+		tamper(&dir, &lyrics_file, corrupt);
+		assert_eq!(load(&dir, &lyrics_file), None, "{name}");
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn load_drops_corrupted_history() {
+		assert_dropped("current", |history| history["current"] = json!(3));
+		assert_dropped("parent", |history| {
+			history["entries"][0]["parent"] = json!(1)
+		});
+		assert_dropped("line-index", |history| {
+			history["entries"][2]["edit"]["backwards_action"]["SetText"]["idx"] = json!(7)
+		});
+
+		assert_dropped("word-index", |history| words(history)[1][0] = json!(50));
+		assert_dropped("word-order", |history| words(history)[0][0] = json!(6));
+		assert_dropped("timestamp", |history| {
+			words(history)[1][1] = json!("00:01.5000")
+		});
+	}
+
+	/// Lines `a` and `b`, with line 2 set to `2` and then, on another branch, to `3`
+	fn branched() -> (History, Lyrics, TimeIndex) {
+		let mut lyrics = parse("[00:01.00]a\n[00:02.00]b\n");
+		let mut time_index = TimeIndex::new(&lyrics);
+		let mut history = History::default();
+		set_text(&mut history, &mut lyrics, &mut time_index, 0, "1");
+		set_text(&mut history, &mut lyrics, &mut time_index, 1, "2");
+		history.undo(&mut lyrics, &mut time_index).unwrap();
+		set_text(&mut history, &mut lyrics, &mut time_index, 1, "3");
+		(history, lyrics, time_index)
+	}
+
+	#[test]
+	fn edits_after_undo_start_a_branch() {
+		let (history, lyrics, _) = branched();
+		let parents: Vec<_> = history.entries().iter().map(HistoryEntry::parent).collect();
+		assert_eq!(parents, [None, Some(0), Some(0)]);
+		assert_eq!(history.current(), Some(2));
+		assert_eq!(texts(&lyrics), ["1", "3"]);
+
+		assert!(history.is_reached(None));
+		assert!(history.is_reached(Some(0)));
+		assert!(history.is_reached(Some(2)));
+		assert!(!history.is_reached(Some(1)));
+	}
+
+	#[test]
+	fn redo_follows_the_last_visited_branch() {
+		let (mut history, mut lyrics, mut time_index) = branched();
+		history.undo(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(texts(&lyrics), ["1", "b"]);
+		history.redo(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(texts(&lyrics), ["1", "3"]);
+
+		history
+			.jump_to(Some(1), &mut lyrics, &mut time_index)
+			.unwrap();
+		history.undo(&mut lyrics, &mut time_index).unwrap();
+		history.redo(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(history.current(), Some(1));
+		assert_eq!(texts(&lyrics), ["1", "2"]);
+
+		// nothing left to redo
+		history.redo(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(history.current(), Some(1));
+	}
+
+	#[test]
+	fn jumps_across_branches() {
+		let (mut history, mut lyrics, mut time_index) = branched();
+		history
+			.jump_to(Some(1), &mut lyrics, &mut time_index)
+			.unwrap();
+		assert_eq!(texts(&lyrics), ["1", "2"]);
+		history.jump_to(None, &mut lyrics, &mut time_index).unwrap();
+		assert_eq!(history.current(), None);
+		assert_eq!(texts(&lyrics), ["a", "b"]);
+		history
+			.jump_to(Some(2), &mut lyrics, &mut time_index)
+			.unwrap();
+		assert_eq!(texts(&lyrics), ["1", "3"]);
+
+		assert!(
+			history
+				.jump_to(Some(3), &mut lyrics, &mut time_index)
+				.is_err()
+		);
+		assert_eq!(history.current(), Some(2));
+	}
+
+	#[test]
+	fn merges_into_the_last_edit_until_it_has_a_branch() {
+		let mut lyrics = parse("[00:01.00]a\n[00:02.00]b\n");
+		let mut time_index = TimeIndex::new(&lyrics);
+		let mut history = History::default();
+		set_text(&mut history, &mut lyrics, &mut time_index, 0, "x");
+		let edit = text_edit(&mut lyrics, &mut time_index, 0, "xy");
+		history.push_merged(edit, "type".to_owned());
+		assert_eq!(history.entries().len(), 1);
+		assert_eq!(history.entries()[0].description(), "type");
+
+		history.undo(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(texts(&lyrics), ["a", "b"]);
+		history.redo(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(texts(&lyrics), ["xy", "b"]);
+
+		set_text(&mut history, &mut lyrics, &mut time_index, 1, "z");
+		history.undo(&mut lyrics, &mut time_index).unwrap();
+		let edit = text_edit(&mut lyrics, &mut time_index, 0, "xyz");
+		history.push_merged(edit, "type".to_owned());
+		assert_eq!(history.entries().len(), 3);
+		assert_eq!(history.entries()[2].parent(), Some(0));
+		history.undo(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(texts(&lyrics), ["xy", "b"]);
+	}
 }
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+use crate::lyrics::{LyricLine, TimestampPrecision};
+
+/// How the time between two synced lines is spread across the unsynced lines between them
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolation {
+	/// by the display width of their text, so longer lines last longer
+	#[default]
+	Width,
+	/// evenly, every line lasts the same time
+	Even,
+}
+
+impl Interpolation {
+	/// Gives the unsynced lines between two synced lines timestamps in between theirs. Each line
+	/// from the synced one before them on lasts a share of the time until the next synced line.
+	pub fn fill(self, lines: &mut [LyricLine], precision: TimestampPrecision) {
+		let synced: Vec<_> = lines
+			.iter()
+			.enumerate()
+			.filter_map(|(idx, line)| line.timestamp().map(|timestamp| (idx, timestamp.time())))
+			.collect();
+
+		for pair in synced.windows(2) {
+			let [(prev, prev_time), (next, next_time)] = pair else {
+				continue;
+			};
+			if next - prev < 2 || next_time <= prev_time {
+				continue;
+			}
+			let mut weights: Vec<_> = lines[*prev..*next]
+				.iter()
+				.map(|line| match self {
+					Interpolation::Width => line.text().width() as u32,
+					Interpolation::Even => 1,
+				})
+				.collect();
+			if weights.iter().all(|weight| *weight == 0) {
+				weights.fill(1);
+			}
+			let total: u32 = weights.iter().sum();
+
+			let gap = *next_time - *prev_time;
+			let mut elapsed = 0;
+			for (line, weight) in lines[prev + 1..*next].iter_mut().zip(&weights) {
+				elapsed += weight;
+				let time = *prev_time + gap.mul_f64(elapsed as f64 / total as f64);
+				line.set_timestamp(Some(precision.truncate(time)));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+
+	fn lines(lines: &[(Option<u64>, &str)]) -> Vec<LyricLine> {
+		lines
+			.iter()
+			.map(|(millis, text)| {
+				LyricLine::new(
+					millis.map(|millis| Duration::from_millis(millis).into()),
+					(*text).to_owned(),
+				)
+			})
+			.collect()
+	}
+
+	fn times(lines: &[LyricLine]) -> Vec<Option<u64>> {
+		lines
+			.iter()
+			.map(|line| line.timestamp().map(|t| t.time().as_millis() as u64))
+			.collect()
+	}
+
+	#[test]
+	fn spreads_by_width() {
+		let mut lyrics = lines(&[
+			(Some(1000), "aa"),
+			(None, "bbbb"),
+			(None, "cc"),
+			(Some(9000), "d"),
+		]);
+		Interpolation::Width.fill(&mut lyrics, TimestampPrecision::Millis);
+		assert_eq!(
+			times(&lyrics),
+			[Some(1000), Some(3000), Some(7000), Some(9000)]
+		);
+	}
+
+	#[test]
+	fn spreads_evenly_at_the_precision() {
+		let input = [
+			(Some(0), "a"),
+			(None, "bbbb"),
+			(None, "cc"),
+			(Some(8000), "d"),
+		];
+		let mut lyrics = lines(&input);
+		Interpolation::Even.fill(&mut lyrics, TimestampPrecision::Millis);
+		assert_eq!(
+			times(&lyrics),
+			[Some(0), Some(2666), Some(5333), Some(8000)]
+		);
+
+		let mut lyrics = lines(&input);
+		Interpolation::Even.fill(&mut lyrics, TimestampPrecision::Centis);
+		assert_eq!(
+			times(&lyrics),
+			[Some(0), Some(2660), Some(5330), Some(8000)]
+		);
+	}
+
+	#[test]
+	fn empty_lines_are_spread_evenly() {
+		let mut lyrics = lines(&[(Some(0), ""), (None, ""), (None, ""), (Some(3000), "")]);
+		Interpolation::Width.fill(&mut lyrics, TimestampPrecision::Millis);
+		assert_eq!(
+			times(&lyrics),
+			[Some(0), Some(1000), Some(2000), Some(3000)]
+		);
+	}
+
+	#[test]
+	fn leaves_lines_it_cannot_place() {
+		let mut lyrics = lines(&[
+			(None, "intro"),
+			(Some(5000), "a"),
+			(None, "between swapped lines"),
+			(Some(2000), "b"),
+			(Some(3000), "c"),
+			(None, "outro"),
+		]);
+		Interpolation::Width.fill(&mut lyrics, TimestampPrecision::Millis);
+		assert_eq!(
+			times(&lyrics),
+			[None, Some(5000), None, Some(2000), Some(3000), None]
+		);
+	}
+}
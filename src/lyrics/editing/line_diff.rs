@@ -0,0 +1,136 @@
+use std::ops::Range;
+
+/// Above this many line pairs, the lines between the common start and end are treated as a single
+/// changed run instead of searching for the lines they still have in common
+const MAX_COMPARISONS: usize = 4_000_000;
+
+/// A run of lines which differs between two versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+	pub old: Range<usize>,
+	pub new: Range<usize>,
+}
+
+/// The runs of lines to replace to turn `old` into `new`, in order, keeping the longest common
+/// subsequence of lines untouched
+pub fn hunks<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Hunk> {
+	let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+	let suffix = old[prefix..]
+		.iter()
+		.rev()
+		.zip(new[prefix..].iter().rev())
+		.take_while(|(a, b)| a == b)
+		.count();
+	let old_mid = &old[prefix..old.len() - suffix];
+	let new_mid = &new[prefix..new.len() - suffix];
+
+	if old_mid.is_empty() && new_mid.is_empty() {
+		return Vec::new();
+	}
+	if old_mid.is_empty()
+		|| new_mid.is_empty()
+		|| old_mid.len().saturating_mul(new_mid.len()) > MAX_COMPARISONS
+	{
+		return vec![Hunk {
+			old: prefix..prefix + old_mid.len(),
+			new: prefix..prefix + new_mid.len(),
+		}];
+	}
+
+	// lengths of the longest common subsequences of all suffixes
+	let width = new_mid.len() + 1;
+	let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+	for i in (0..old_mid.len()).rev() {
+		for j in (0..new_mid.len()).rev() {
+			lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+				lcs[(i + 1) * width + j + 1] + 1
+			} else {
+				lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+			};
+		}
+	}
+
+	let mut hunks = Vec::new();
+	let mut current: Option<Hunk> = None;
+	let (mut i, mut j) = (0, 0);
+	while i < old_mid.len() || j < new_mid.len() {
+		if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+			hunks.extend(current.take());
+			i += 1;
+			j += 1;
+			continue;
+		}
+
+		let hunk = current.get_or_insert(Hunk {
+			old: prefix + i..prefix + i,
+			new: prefix + j..prefix + j,
+		});
+		if j == new_mid.len()
+			|| (i < old_mid.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+		{
+			i += 1;
+			hunk.old.end += 1;
+		} else {
+			j += 1;
+			hunk.new.end += 1;
+		}
+	}
+	hunks.extend(current);
+	hunks
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hunk(old: Range<usize>, new: Range<usize>) -> Hunk {
+		Hunk { old, new }
+	}
+
+	#[test]
+	fn same_lines_have_no_hunks() {
+		assert_eq!(hunks(&[1, 2, 3], &[1, 2, 3]), vec![]);
+		assert_eq!(hunks::<u8>(&[], &[]), vec![]);
+	}
+
+	#[test]
+	fn insertion() {
+		assert_eq!(hunks(&[1, 2, 3], &[1, 4, 5, 2, 3]), vec![hunk(1..1, 1..3)]);
+		assert_eq!(
+			hunks(&[1, 2], &[0, 1, 2, 3]),
+			vec![hunk(0..0, 0..1), hunk(2..2, 3..4)]
+		);
+	}
+
+	#[test]
+	fn deletion() {
+		assert_eq!(hunks(&[1, 2, 3, 4], &[1, 4]), vec![hunk(1..3, 1..1)]);
+		assert_eq!(
+			hunks(&[0, 1, 2, 3], &[1, 2]),
+			vec![hunk(0..1, 0..0), hunk(3..4, 2..2)]
+		);
+	}
+
+	#[test]
+	fn replacement() {
+		assert_eq!(hunks(&[1, 2, 3], &[1, 5, 3]), vec![hunk(1..2, 1..2)]);
+		assert_eq!(
+			hunks(&[1, 2, 3, 4, 5], &[1, 6, 3, 7, 8, 5]),
+			vec![hunk(1..2, 1..2), hunk(3..4, 3..5)]
+		);
+	}
+
+	#[test]
+	fn too_many_comparisons_give_one_hunk() {
+		let old: Vec<_> = (0..2002).collect();
+		let mut new = old.clone();
+		new.reverse();
+		assert!(old.len() * new.len() > MAX_COMPARISONS);
+		assert_eq!(hunks(&old, &new), vec![hunk(0..2002, 0..2002)]);
+
+		// the common start and end are still left out
+		let old: Vec<_> = [0].into_iter().chain(old).chain([3000]).collect();
+		let new: Vec<_> = [0].into_iter().chain(new).chain([3000]).collect();
+		assert_eq!(hunks(&old, &new), vec![hunk(1..2003, 1..2003)]);
+	}
+}
@@ -1,7 +1,14 @@
 mod edit;
 mod edit_action;
 mod history;
+mod interpolation;
+mod line_diff;
+mod split_timestamp;
+mod time_stretch;
 
 pub use edit::Edit;
 pub use edit_action::EditAction;
 pub use history::History;
+pub use interpolation::Interpolation;
+pub use split_timestamp::SplitTimestamp;
+pub use time_stretch::{StretchAnchor, TimeStretch};
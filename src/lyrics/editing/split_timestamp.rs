@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Which timestamp the new line gets when a line is split
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplitTimestamp {
+	/// the word timestamp at the start of the new line, if there is one
+	#[default]
+	Word,
+	/// the current playback position
+	Playback,
+	/// none, the new line is unsynced
+	None,
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use color_eyre::eyre;
+
+/// A line whose timestamp should be at another time, one of the two a [`TimeStretch`] is made from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StretchAnchor {
+	pub line: u16,
+	/// the timestamp the line has
+	pub from: Duration,
+	/// the time the line should be at
+	pub to: Duration,
+}
+
+/// Linear remapping of times which moves the times of two anchors to where they should be, for
+/// lyrics synced to a different release of the same song
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeStretch {
+	from: Duration,
+	to: Duration,
+	factor: f64,
+}
+
+impl TimeStretch {
+	pub fn new(first: StretchAnchor, second: StretchAnchor) -> eyre::Result<Self> {
+		if first.from == second.from {
+			return Err(eyre::eyre!("Anchor lines have the same timestamp"));
+		}
+		let factor = (second.to.as_secs_f64() - first.to.as_secs_f64())
+			/ (second.from.as_secs_f64() - first.from.as_secs_f64());
+		if factor <= 0. {
+			return Err(eyre::eyre!("Anchor lines would swap order"));
+		}
+		Ok(Self {
+			from: first.from,
+			to: first.to,
+			factor,
+		})
+	}
+
+	/// Where a time ends up, times which would end up before the start of the track are set to it
+	pub fn apply(&self, time: Duration) -> Duration {
+		let millis = self.to.as_millis() as f64
+			+ (time.as_millis() as f64 - self.from.as_millis() as f64) * self.factor;
+		Duration::from_millis(millis.round().max(0.) as u64)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn anchor(line: u16, from: u64, to: u64) -> StretchAnchor {
+		StretchAnchor {
+			line,
+			from: Duration::from_millis(from),
+			to: Duration::from_millis(to),
+		}
+	}
+
+	fn ms(millis: u64) -> Duration {
+		Duration::from_millis(millis)
+	}
+
+	#[test]
+	fn moves_anchors_to_their_times() {
+		let stretch =
+			TimeStretch::new(anchor(0, 10_000, 12_000), anchor(9, 110_000, 122_000)).unwrap();
+		assert_eq!(stretch.apply(ms(10_000)), ms(12_000));
+		assert_eq!(stretch.apply(ms(110_000)), ms(122_000));
+		assert_eq!(stretch.apply(ms(60_000)), ms(67_000));
+		// times outside the anchors are stretched too
+		assert_eq!(stretch.apply(ms(210_000)), ms(232_000));
+		assert_eq!(stretch.apply(ms(5_000)), ms(6_500));
+	}
+
+	#[test]
+	fn anchors_may_be_given_in_either_order() {
+		let first = anchor(0, 10_000, 8_000);
+		let second = anchor(5, 20_000, 13_000);
+		let forward = TimeStretch::new(first, second).unwrap();
+		let backward = TimeStretch::new(second, first).unwrap();
+		for time in [0, 9_000, 15_000, 30_000] {
+			assert_eq!(forward.apply(ms(time)), backward.apply(ms(time)));
+		}
+		assert_eq!(forward.apply(ms(15_000)), ms(10_500));
+	}
+
+	#[test]
+	fn stops_at_the_start_of_the_track() {
+		let stretch =
+			TimeStretch::new(anchor(0, 10_000, 1_000), anchor(1, 20_000, 11_000)).unwrap();
+		assert_eq!(stretch.apply(ms(2_000)), Duration::ZERO);
+		assert_eq!(stretch.apply(ms(9_000)), Duration::ZERO);
+		assert_eq!(stretch.apply(ms(9_500)), ms(500));
+	}
+
+	#[test]
+	fn rejects_anchors_which_cannot_be_stretched_between() {
+		assert!(TimeStretch::new(anchor(0, 10_000, 1_000), anchor(1, 10_000, 5_000)).is_err());
+		assert!(TimeStretch::new(anchor(0, 10_000, 5_000), anchor(1, 20_000, 4_000)).is_err());
+		assert!(TimeStretch::new(anchor(0, 10_000, 5_000), anchor(1, 20_000, 5_000)).is_err());
+	}
+}
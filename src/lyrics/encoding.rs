@@ -0,0 +1,190 @@
+use std::{fmt, fs, io, path::Path};
+
+use chardetng::EncodingDetector;
+use color_eyre::eyre;
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+
+/// How a lyrics file is encoded, so it can be written back the way it was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEncoding {
+	pub encoding: &'static Encoding,
+	/// whether the file starts with a byte order mark
+	pub bom: bool,
+	/// whether lines end in `\r\n`
+	pub crlf: bool,
+}
+
+impl Default for FileEncoding {
+	fn default() -> Self {
+		Self {
+			encoding: UTF_8,
+			bom: false,
+			crlf: false,
+		}
+	}
+}
+
+impl FileEncoding {
+	/// Reads a text file, see [`FileEncoding::decode`]
+	pub fn read(path: &Path) -> io::Result<(String, Self)> {
+		Ok(Self::decode(&fs::read(path)?))
+	}
+
+	/// Decodes text in the encoding given by its byte order mark, or else UTF-8 if it is valid,
+	/// or else the legacy encoding it most likely is in
+	pub fn decode(bytes: &[u8]) -> (String, Self) {
+		let (encoding, bom) = match Encoding::for_bom(bytes) {
+			Some((encoding, _)) => (encoding, true),
+			None if str::from_utf8(bytes).is_ok() => (UTF_8, false),
+			None => {
+				let mut detector = EncodingDetector::new();
+				detector.feed(bytes, true);
+				(detector.guess(None, true), false)
+			}
+		};
+		// the bom is stripped while decoding
+		let (text, _, _) = encoding.decode(bytes);
+		let crlf = text.contains("\r\n");
+
+		(
+			text.into_owned(),
+			Self {
+				encoding,
+				bom,
+				crlf,
+			},
+		)
+	}
+
+	/// Encodes text with `\n` line endings, fails if it contains characters the encoding does not
+	/// have
+	pub fn encode(&self, text: &str) -> eyre::Result<Vec<u8>> {
+		let mut text = if self.crlf {
+			text.replace('\n', "\r\n")
+		} else {
+			text.to_owned()
+		};
+		if self.bom {
+			text.insert(0, '\u{feff}');
+		}
+
+		// encoding_rs only encodes to UTF-16 as UTF-8
+		if self.encoding == UTF_16LE {
+			return Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect());
+		}
+		if self.encoding == UTF_16BE {
+			return Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect());
+		}
+		let (bytes, _, unmappable) = self.encoding.encode(&text);
+		if unmappable {
+			return Err(eyre::eyre!(
+				"The lyrics contain characters which cannot be written in {}, set save-encoding to utf8 to convert the file",
+				self.encoding.name()
+			));
+		}
+		Ok(bytes.into_owned())
+	}
+
+	/// The same byte order mark and line endings, but in UTF-8
+	pub fn to_utf8(self) -> Self {
+		Self {
+			encoding: UTF_8,
+			..self
+		}
+	}
+}
+
+impl fmt::Display for FileEncoding {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.encoding.name())?;
+		if self.bom {
+			f.write_str(" BOM")?;
+		}
+		if self.crlf {
+			f.write_str(" CRLF")?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+	use super::*;
+
+	#[test]
+	fn decodes_by_byte_order_mark() {
+		let (text, encoding) = FileEncoding::decode(b"\xef\xbb\xbf[00:01.00]caf\xc3\xa9\n");
+		assert_eq!(text, "[00:01.00]café\n");
+		assert_eq!((encoding.encoding, encoding.bom), (UTF_8, true));
+
+		let (text, encoding) = FileEncoding::decode(b"\xff\xfea\x00\n\x00");
+		assert_eq!(text, "a\n");
+		assert_eq!((encoding.encoding, encoding.bom), (UTF_16LE, true));
+
+		let (text, encoding) = FileEncoding::decode(b"\xfe\xff\x00a\x00\n");
+		assert_eq!(text, "a\n");
+		assert_eq!((encoding.encoding, encoding.bom), (UTF_16BE, true));
+	}
+
+	#[test]
+	fn detects_line_endings() {
+		let (_, encoding) = FileEncoding::decode(b"a\r\nb\r\n");
+		assert!(encoding.crlf);
+		let (_, encoding) = FileEncoding::decode(b"a\nb\n");
+		assert_eq!(encoding, FileEncoding::default());
+	}
+
+	#[test]
+	fn detects_legacy_encodings() {
+		let (text, encoding, _) = SHIFT_JIS.encode("[00:01.00]きらきら光る夜空の星よ\n");
+		let (decoded, detected) = FileEncoding::decode(&text);
+		assert_eq!(detected.encoding, SHIFT_JIS);
+		assert_eq!(decoded, "[00:01.00]きらきら光る夜空の星よ\n");
+		assert_eq!(encoding, SHIFT_JIS);
+
+		let (text, _, _) = WINDOWS_1252.encode("[00:01.00]Là où le café était très déjà prêt\n");
+		let (decoded, detected) = FileEncoding::decode(&text);
+		assert_eq!(detected.encoding, WINDOWS_1252);
+		assert_eq!(decoded, "[00:01.00]Là où le café était très déjà prêt\n");
+	}
+
+	#[test]
+	fn encodes_the_way_it_was_read() {
+		for bytes in [
+			b"\xef\xbb\xbfa\r\nb\r\n".as_slice(),
+			b"\xff\xfea\x00\r\x00\n\x00",
+			b"\xfe\xff\x00a\x00\n",
+			b"caf\xe9 au lait tr\xe8s chaud\n",
+		] {
+			let (text, encoding) = FileEncoding::decode(bytes);
+			let text = text.replace("\r\n", "\n");
+			assert_eq!(encoding.encode(&text).unwrap(), bytes, "{encoding}");
+		}
+	}
+
+	#[test]
+	fn refuses_characters_the_encoding_does_not_have() {
+		let encoding = FileEncoding {
+			encoding: WINDOWS_1252,
+			..FileEncoding::default()
+		};
+		assert!(encoding.encode("日本").is_err());
+		assert_eq!(
+			encoding.to_utf8().encode("日本").unwrap(),
+			"日本".as_bytes()
+		);
+	}
+
+	#[test]
+	fn displays_name_and_flags() {
+		let encoding = FileEncoding {
+			encoding: UTF_16LE,
+			bom: true,
+			crlf: true,
+		};
+		assert_eq!(encoding.to_string(), "UTF-16LE BOM CRLF");
+		assert_eq!(FileEncoding::default().to_string(), "UTF-8");
+	}
+}
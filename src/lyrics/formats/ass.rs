@@ -0,0 +1,201 @@
+use std::{io::Write, time::Duration};
+
+use color_eyre::eyre;
+
+use super::{
+	WriteOptions,
+	cue::{Cue, cues},
+};
+use crate::lyrics::{Lyrics, TagType};
+
+/// Styles section used when none is configured, the events use the style named `Default`
+pub const DEFAULT_STYLES: &str = "\
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, \
+Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, \
+Alignment, MarginL, MarginR, MarginV, Encoding
+Style: Default,Arial,64,&H00FFFFFF,&H0000A5FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,1,\
+2,60,60,60,1
+";
+
+/// Writes every synced line as a dialogue event, with word timestamps as `{\k}` karaoke tags
+pub fn write(lyrics: &Lyrics, writer: &mut impl Write, options: &WriteOptions) -> eyre::Result<()> {
+	let title = match (lyrics.tag(&TagType::Artist), lyrics.tag(&TagType::Title)) {
+		(Some(artist), Some(title)) => Some(format!("{artist} - {title}")),
+		(None, Some(title)) => Some(title.to_owned()),
+		(Some(artist), None) => Some(artist.to_owned()),
+		(None, None) => None,
+	};
+
+	writeln!(writer, "[Script Info]")?;
+	if let Some(title) = title {
+		writeln!(writer, "Title: {title}")?;
+	}
+	writeln!(writer, "ScriptType: v4.00+")?;
+	writeln!(writer, "WrapStyle: 0")?;
+	writeln!(writer, "ScaledBorderAndShadow: yes")?;
+	writeln!(writer)?;
+	writeln!(writer, "{}", options.ass_styles.trim_end())?;
+	writeln!(writer)?;
+	writeln!(writer, "[Events]")?;
+	writeln!(
+		writer,
+		"Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text"
+	)?;
+
+	for cue in cues(lyrics, options) {
+		writeln!(
+			writer,
+			"Dialogue: 0,{},{},Default,{},0,0,0,,{}",
+			format_time(cue.start),
+			format_time(cue.end),
+			cue.line
+				.voice()
+				.map(|voice| voice.to_string())
+				.unwrap_or_default(),
+			event_text(&cue)
+		)?;
+	}
+
+	writer.flush()?;
+	Ok(())
+}
+
+/// Line text with a `{\k}` tag before every timed word, lasting until the next word starts. Word
+/// timestamps past the end of the trimmed text only end the word before them.
+fn event_text(cue: &Cue) -> String {
+	let text = cue.line.text().trim_end();
+	let words = cue.line.word_timestamps();
+	let Some((first, first_timestamp)) = words.first() else {
+		return escape(text);
+	};
+	let first = (*first).min(text.len());
+
+	let mut s = String::with_capacity(text.len() + words.len() * 8);
+	if first > 0 || first_timestamp.time() > cue.start {
+		s.push_str(&karaoke_tag(cue.start, first_timestamp.time()));
+		s.push_str(&escape(&text[..first]));
+	}
+	for (i, (idx, timestamp)) in words.iter().enumerate() {
+		if *idx >= text.len() {
+			break;
+		}
+		let (word_end, end) = match words.get(i + 1) {
+			Some((next_idx, next)) => ((*next_idx).min(text.len()), next.time()),
+			None => (text.len(), cue.end),
+		};
+		s.push_str(&karaoke_tag(timestamp.time(), end));
+		s.push_str(&escape(&text[(*idx).min(word_end)..word_end]));
+	}
+	s
+}
+
+fn karaoke_tag(start: Duration, end: Duration) -> String {
+	let centis = |time: Duration| time.as_millis() / 10;
+	format!("{{\\k{}}}", centis(end).saturating_sub(centis(start)))
+}
+
+/// Formats as `h:mm:ss.cc`
+fn format_time(time: Duration) -> String {
+	let secs = time.as_secs();
+	format!(
+		"{}:{:0>2}:{:0>2}.{:0>2}",
+		secs / 3600,
+		secs / 60 % 60,
+		secs % 60,
+		time.subsec_millis() / 10
+	)
+}
+
+/// Keeps braces in the text from being read as override tags
+fn escape(s: &str) -> String {
+	s.replace('{', "\\{").replace('}', "\\}")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::lyrics::{Timestamp, TimestampPrecision};
+
+	fn parse(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	fn events(lyrics: &Lyrics) -> Vec<String> {
+		let options = WriteOptions {
+			precision: TimestampPrecision::Keep,
+			duration: Some(Duration::from_secs(10)),
+			ass_styles: DEFAULT_STYLES,
+		};
+		let mut out = Vec::new();
+		write(lyrics, &mut out, &options).unwrap();
+		String::from_utf8(out)
+			.unwrap()
+			.lines()
+			.filter_map(|line| line.strip_prefix("Dialogue: "))
+			.map(str::to_owned)
+			.collect()
+	}
+
+	#[test]
+	fn writes_header_and_events() {
+		let lyrics =
+			parse("[ar:someone]\n[ti:song]\n[00:01.00]first {line}\n[00:02.50]v2: second\n");
+		let mut out = Vec::new();
+		let options = WriteOptions {
+			precision: TimestampPrecision::Keep,
+			duration: None,
+			ass_styles: DEFAULT_STYLES,
+		};
+		write(&lyrics, &mut out, &options).unwrap();
+		let out = String::from_utf8(out).unwrap();
+		assert!(out.starts_with("[Script Info]\nTitle: someone - song\n"));
+		assert!(out.contains("\n[V4+ Styles]\n"));
+		assert_eq!(
+			events(&lyrics),
+			[
+				"0,0:00:01.00,0:00:02.50,Default,,0,0,0,,first \\{line\\}",
+				"0,0:00:02.50,0:00:10.00,Default,v2,0,0,0,,second",
+			]
+		);
+	}
+
+	#[test]
+	fn writes_karaoke_tags() {
+		let lyrics = parse("[00:01.00]lead <00:01.20>one <00:01.70>two\n[00:03.00]next\n");
+		assert_eq!(
+			events(&lyrics)[0],
+			"0,0:00:01.00,0:00:03.00,Default,,0,0,0,,{\\k20}lead {\\k50}one {\\k130}two"
+		);
+	}
+
+	#[test]
+	fn trailing_word_timestamp_ends_the_last_word() {
+		let lyrics = parse("[00:01.00]<00:01.00>hello <00:01.50>world <00:02.00>\n");
+		assert_eq!(
+			events(&lyrics),
+			["0,0:00:01.00,0:00:10.00,Default,,0,0,0,,{\\k50}hello {\\k50}world"]
+		);
+	}
+
+	#[test]
+	fn ignores_word_timestamps_past_the_text() {
+		let mut lyrics = parse("[00:01.00]short\n");
+		let words = ["00:01.00", "00:01.50", "00:02.00"].map(|s| s.parse::<Timestamp>().unwrap());
+		lyrics.set_text_at_line(
+			0,
+			"short ".to_owned(),
+			vec![
+				(0, words[0].clone()),
+				(40, words[1].clone()),
+				(80, words[2].clone()),
+			],
+		);
+		assert_eq!(
+			events(&lyrics),
+			["0,0:00:01.00,0:00:10.00,Default,,0,0,0,,{\\k50}short"]
+		);
+	}
+}
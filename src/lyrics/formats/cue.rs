@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use super::WriteOptions;
+use crate::lyrics::{LyricLine, Lyrics};
+
+/// How long the last cue lasts when the length of the track is not known
+const LAST_CUE_LENGTH: Duration = Duration::from_secs(5);
+
+/// A line with a start and end time, as used by subtitle formats
+pub struct Cue {
+	pub start: Duration,
+	pub end: Duration,
+	pub line: LyricLine,
+}
+
+/// Turns every synced line with text into a cue which lasts until the next synced line starts,
+/// in order of time, with repeated lines expanded and the offset applied
+pub fn cues(lyrics: &Lyrics, options: &WriteOptions) -> Vec<Cue> {
+	let mut lyrics = lyrics.clone();
+	lyrics.expand_repeats();
+	lyrics.bake_offset();
+	let mut timed_lines: Vec<_> = lyrics
+		.lines()
+		.iter()
+		.filter_map(|line| line.timestamp().map(|timestamp| (timestamp.time(), line)))
+		.collect();
+	timed_lines.sort_by_key(|(start, _)| *start);
+
+	timed_lines
+		.iter()
+		.enumerate()
+		.filter(|(_, (_, line))| !line.text().trim().is_empty())
+		.map(|(i, &(start, line))| {
+			let end = timed_lines
+				.get(i + 1)
+				.map(|(next, _)| *next)
+				.or(options.duration)
+				.unwrap_or(start + LAST_CUE_LENGTH);
+			let end = options.duration.map_or(end, |duration| end.min(duration));
+			Cue {
+				start,
+				end: end.max(start),
+				line: line.clone(),
+			}
+		})
+		.collect()
+}
+
+/// Formats as `hh:mm:ss.mmm` with the given separator before the millis
+pub fn format_time(time: Duration, separator: char) -> String {
+	let secs = time.as_secs();
+	format!(
+		"{:0>2}:{:0>2}:{:0>2}{separator}{:0>3}",
+		secs / 3600,
+		secs / 60 % 60,
+		secs % 60,
+		time.subsec_millis()
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::lyrics::TimestampPrecision;
+
+	fn cue_times(s: &str, duration: Option<Duration>) -> Vec<(u64, u64, String)> {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		let options = WriteOptions {
+			precision: TimestampPrecision::Keep,
+			duration,
+			ass_styles: "",
+		};
+		cues(&lyrics, &options)
+			.into_iter()
+			.map(|cue| {
+				(
+					cue.start.as_millis() as u64,
+					cue.end.as_millis() as u64,
+					cue.line.text().to_owned(),
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn cues_are_in_order_of_time() {
+		assert_eq!(
+			cue_times("[00:03.00]c\n[00:01.00]a\n[00:02.00]b\n", None),
+			[
+				(1000, 2000, "a".to_owned()),
+				(2000, 3000, "b".to_owned()),
+				(3000, 8000, "c".to_owned()),
+			]
+		);
+	}
+
+	#[test]
+	fn cues_end_at_the_track_duration() {
+		assert_eq!(
+			cue_times(
+				"[00:01.00]a\n[00:05.00]b\n[00:12.00]past the end\n",
+				Some(Duration::from_secs(4))
+			),
+			[
+				(1000, 4000, "a".to_owned()),
+				(5000, 5000, "b".to_owned()),
+				(12000, 12000, "past the end".to_owned()),
+			]
+		);
+	}
+}
@@ -0,0 +1,276 @@
+use std::{borrow::Cow, fs::File, path::Path, time::Duration};
+
+use color_eyre::eyre;
+use lofty::{
+	TextEncoding,
+	config::{ParseOptions, WriteOptions as TagWriteOptions},
+	file::{AudioFile, FileType, TaggedFileExt},
+	id3::v2::{
+		BinaryFrame, Frame, FrameId, Id3v2Tag, SyncTextContentType, SynchronizedTextFrame,
+		TimestampFormat, UnsynchronizedTextFrame,
+	},
+	iff::wav::WavFile,
+	mpeg::MpegFile,
+	probe::Probe,
+	tag::{ItemKey, Tag, TagExt},
+};
+
+use super::{LyricsFormat, WriteOptions};
+use crate::lyrics::{LyricLine, Lyrics};
+
+const USLT: FrameId = FrameId::Valid(Cow::Borrowed("USLT"));
+const SYLT: FrameId = FrameId::Valid(Cow::Borrowed("SYLT"));
+/// vorbis comment some taggers use instead of `LYRICS`
+const UNSYNCED_LYRICS: &str = "UNSYNCEDLYRICS";
+
+/// Reads the lyrics embedded in the tags of an audio file, preferring synced lyrics
+pub fn read(audio_path: &Path) -> eyre::Result<Option<Lyrics>> {
+	let id3v2 = match Probe::open(audio_path)?.guess_file_type()?.file_type() {
+		Some(FileType::Mpeg) => {
+			MpegFile::read_from(&mut File::open(audio_path)?, ParseOptions::new())?
+				.id3v2()
+				.cloned()
+		}
+		Some(FileType::Wav) => {
+			WavFile::read_from(&mut File::open(audio_path)?, ParseOptions::new())?
+				.id3v2()
+				.cloned()
+		}
+		_ => None,
+	};
+
+	let mut candidates = Vec::new();
+	if let Some(tag) = &id3v2 {
+		candidates.extend(tag.unsync_text().map(|frame| parse_text(&frame.content)));
+		candidates.extend(read_sylt(tag));
+	} else {
+		let tagged_file = lofty::read_from_path(audio_path)?;
+		for tag in tagged_file.tags() {
+			candidates.extend(
+				tag.get_strings(&ItemKey::Lyrics)
+					.chain(tag.get_strings(&ItemKey::Unknown(UNSYNCED_LYRICS.to_owned())))
+					.map(parse_text),
+			);
+		}
+	}
+
+	let candidates = candidates.into_iter().collect::<eyre::Result<Vec<_>>>()?;
+	let synced = candidates
+		.iter()
+		.position(|lyrics| lyrics.sync_percentage() > 0);
+	Ok(synced
+		.or((!candidates.is_empty()).then_some(0))
+		.map(|i| candidates[i].clone()))
+}
+
+fn parse_text(text: &str) -> eyre::Result<Lyrics> {
+	LyricsFormat::Lrc.read(text.as_bytes())
+}
+
+fn read_sylt(tag: &Id3v2Tag) -> Option<eyre::Result<Lyrics>> {
+	let Some(Frame::Binary(frame)) = tag.get(&SYLT) else {
+		return None;
+	};
+	let frame = match SynchronizedTextFrame::parse(&frame.data, frame.flags()) {
+		Ok(frame) => frame,
+		Err(e) => return Some(Err(e.into())),
+	};
+	if frame.timestamp_format != TimestampFormat::MS {
+		return None;
+	}
+
+	Some(Ok(Lyrics::from_lines(
+		frame
+			.content
+			.into_iter()
+			.map(|(millis, text)| {
+				LyricLine::new(
+					Some(Duration::from_millis(millis as u64).into()),
+					text.trim().to_owned(),
+				)
+			})
+			.collect(),
+	)))
+}
+
+/// Writes the lyrics as lrc text into the lyrics tag of an audio file, and for id3v2 tags also
+/// as a `SYLT` frame for players which only understand that
+pub fn write(lyrics: &Lyrics, audio_path: &Path, options: &WriteOptions) -> eyre::Result<()> {
+	let mut text = Vec::new();
+	lyrics.write_to(&mut text, options.precision)?;
+	let text = String::from_utf8(text)?;
+
+	match Probe::open(audio_path)?.guess_file_type()?.file_type() {
+		Some(FileType::Mpeg) => {
+			let file = MpegFile::read_from(&mut File::open(audio_path)?, ParseOptions::new())?;
+			write_id3v2(file.id3v2().cloned(), lyrics, text, audio_path)
+		}
+		Some(FileType::Wav) => {
+			let file = WavFile::read_from(&mut File::open(audio_path)?, ParseOptions::new())?;
+			write_id3v2(file.id3v2().cloned(), lyrics, text, audio_path)
+		}
+		_ => {
+			let mut tagged_file = lofty::read_from_path(audio_path)?;
+			if tagged_file.primary_tag().is_none() {
+				tagged_file.insert_tag(Tag::new(tagged_file.primary_tag_type()));
+			}
+			let Some(tag) = tagged_file.primary_tag_mut() else {
+				return Err(eyre::eyre!("File does not support tags"));
+			};
+			tag.remove_key(&ItemKey::Unknown(UNSYNCED_LYRICS.to_owned()));
+			tag.insert_text(ItemKey::Lyrics, text);
+			tag.save_to_path(audio_path, TagWriteOptions::default())?;
+			Ok(())
+		}
+	}
+}
+
+fn write_id3v2(
+	tag: Option<Id3v2Tag>,
+	lyrics: &Lyrics,
+	text: String,
+	audio_path: &Path,
+) -> eyre::Result<()> {
+	let mut tag = tag.unwrap_or_default();
+	let _ = tag.remove(&USLT);
+	let _ = tag.remove(&SYLT);
+	tag.insert(Frame::UnsynchronizedText(UnsynchronizedTextFrame::new(
+		TextEncoding::UTF8,
+		*b"XXX",
+		String::new(),
+		text,
+	)));
+
+	let mut lyrics = lyrics.clone();
+	lyrics.expand_repeats();
+	lyrics.bake_offset();
+	let mut content: Vec<_> = lyrics
+		.lines()
+		.iter()
+		.filter_map(|line| {
+			line.timestamp()
+				.map(|timestamp| (timestamp.time().as_millis() as u32, line.text().to_owned()))
+		})
+		.collect();
+	content.sort_by_key(|(millis, _)| *millis);
+	if !content.is_empty() {
+		let frame = SynchronizedTextFrame::new(
+			TextEncoding::UTF8,
+			*b"XXX",
+			TimestampFormat::MS,
+			SyncTextContentType::Lyrics,
+			None,
+			content,
+		);
+		tag.insert(Frame::Binary(BinaryFrame::new(SYLT, frame.as_bytes()?)));
+	}
+
+	tag.save_to_path(audio_path, TagWriteOptions::default())?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, path::PathBuf};
+
+	use super::*;
+	use crate::lyrics::TimestampPrecision;
+
+	/// Writes a short silent wav file without tags
+	fn wav_file(name: &str) -> PathBuf {
+		let path = std::env::temp_dir().join(format!("lrxed-{}-{name}.wav", std::process::id()));
+		let data = [0u8; 16];
+		let mut bytes = Vec::new();
+		bytes.extend(b"RIFF");
+		bytes.extend((36 + data.len() as u32).to_le_bytes());
+		bytes.extend(b"WAVEfmt ");
+		bytes.extend(16u32.to_le_bytes());
+		bytes.extend(1u16.to_le_bytes());
+		bytes.extend(1u16.to_le_bytes());
+		bytes.extend(8000u32.to_le_bytes());
+		bytes.extend(16000u32.to_le_bytes());
+		bytes.extend(2u16.to_le_bytes());
+		bytes.extend(16u16.to_le_bytes());
+		bytes.extend(b"data");
+		bytes.extend((data.len() as u32).to_le_bytes());
+		bytes.extend(data);
+		fs::write(&path, bytes).unwrap();
+		path
+	}
+
+	fn options() -> WriteOptions<'static> {
+		WriteOptions {
+			precision: TimestampPrecision::Keep,
+			duration: None,
+			ass_styles: "",
+		}
+	}
+
+	fn id3v2(path: &Path) -> Id3v2Tag {
+		WavFile::read_from(&mut File::open(path).unwrap(), ParseOptions::new())
+			.unwrap()
+			.id3v2()
+			.cloned()
+			.unwrap()
+	}
+
+	fn texts(lyrics: &Lyrics) -> Vec<(Option<u64>, &str)> {
+		lyrics
+			.lines()
+			.iter()
+			.map(|line| {
+				(
+					line.timestamp().map(|t| t.time().as_millis() as u64),
+					line.text(),
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn untagged_file_has_no_lyrics() {
+		let path = wav_file("untagged");
+		assert!(read(&path).unwrap().is_none());
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn writes_lrc_text_and_synced_frame() {
+		let path = wav_file("embedded");
+		let lyrics = LyricsFormat::Lrc
+			.read(b"[ti:song]\n[00:01.00][00:03.00]la\n[00:02.00]da\n".as_slice())
+			.unwrap();
+		write(&lyrics, &path, &options()).unwrap();
+		// writing again replaces the frames instead of adding more
+		write(&lyrics, &path, &options()).unwrap();
+
+		let tag = id3v2(&path);
+		let uslt: Vec<_> = tag.unsync_text().collect();
+		assert_eq!(uslt.len(), 1);
+		assert_eq!(
+			uslt[0].content,
+			"[ti:song]\n[00:01.00][00:03.00]la\n[00:02.00]da\n"
+		);
+		let sylt = read_sylt(&tag).unwrap().unwrap();
+		assert_eq!(
+			texts(&sylt),
+			[(Some(1000), "la"), (Some(2000), "da"), (Some(3000), "la")]
+		);
+
+		let read_back = read(&path).unwrap().unwrap();
+		assert_eq!(read_back.sync_percentage(), 100);
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn unsynced_lyrics_have_no_synced_frame() {
+		let path = wav_file("unsynced");
+		let lyrics = LyricsFormat::Lrc.read(b"just\ntext\n".as_slice()).unwrap();
+		write(&lyrics, &path, &options()).unwrap();
+
+		assert!(id3v2(&path).get(&SYLT).is_none());
+		let read_back = read(&path).unwrap().unwrap();
+		assert_eq!(texts(&read_back), [(None, "just"), (None, "text")]);
+		fs::remove_file(path).unwrap();
+	}
+}
@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use color_eyre::eyre::{self, OptionExt};
+use serde::Deserialize;
+
+use crate::lyrics::{LyricLine, Lyrics};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Document {
+	/// spotify's color-lyrics response
+	Spotify {
+		lyrics: SpotifyLyrics,
+	},
+	SpotifyLines(SpotifyLyrics),
+	/// musixmatch api response, with the richsync body as a string of json
+	Musixmatch {
+		message: MusixmatchMessage,
+	},
+	RichSync(Vec<RichSyncLine>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpotifyLyrics {
+	#[serde(default)]
+	sync_type: Option<String>,
+	lines: Vec<SpotifyLine>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpotifyLine {
+	start_time_ms: Millis,
+	#[serde(default)]
+	words: String,
+}
+
+/// spotify writes its times as strings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Millis {
+	Number(u64),
+	Text(String),
+}
+
+#[derive(Deserialize)]
+struct MusixmatchMessage {
+	body: MusixmatchBody,
+}
+
+#[derive(Deserialize)]
+struct MusixmatchBody {
+	richsync: MusixmatchRichSync,
+}
+
+#[derive(Deserialize)]
+struct MusixmatchRichSync {
+	richsync_body: String,
+}
+
+#[derive(Deserialize)]
+struct RichSyncLine {
+	/// start time in seconds
+	ts: f64,
+	/// end time in seconds
+	te: f64,
+	/// pieces of text with their start time in seconds relative to the start of the line
+	l: Vec<RichSyncPiece>,
+	/// the full text of the line
+	#[serde(default)]
+	x: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RichSyncPiece {
+	c: String,
+	o: f64,
+}
+
+/// Reads spotify style `lines` with `startTimeMs` and `words`, or musixmatch style richsync lines
+/// with word timing
+pub fn read(s: &str) -> eyre::Result<Lyrics> {
+	let document = serde_json::from_str(s)
+		.map_err(|_| eyre::eyre!("Unrecognised lyrics json, expected spotify lines or richsync"))?;
+	match document {
+		Document::Spotify { lyrics } | Document::SpotifyLines(lyrics) => read_spotify(lyrics),
+		Document::Musixmatch { message } => {
+			read_rich_sync(serde_json::from_str(&message.body.richsync.richsync_body)?)
+		}
+		Document::RichSync(lines) => read_rich_sync(lines),
+	}
+}
+
+fn read_spotify(lyrics: SpotifyLyrics) -> eyre::Result<Lyrics> {
+	let synced = lyrics.sync_type.as_deref() != Some("UNSYNCED");
+	let lines = lyrics
+		.lines
+		.into_iter()
+		.map(|line| {
+			let millis = match line.start_time_ms {
+				Millis::Number(millis) => millis,
+				Millis::Text(millis) => millis.trim().parse()?,
+			};
+			// spotify marks instrumental parts with a lone note
+			let text = if line.words.trim() == "♪" {
+				String::new()
+			} else {
+				line.words
+			};
+			Ok(LyricLine::new(
+				synced.then(|| Duration::from_millis(millis).into()),
+				text,
+			))
+		})
+		.collect::<eyre::Result<_>>()?;
+	Ok(Lyrics::from_lines(lines))
+}
+
+/// A gap between the end of a line and the start of the next is kept as an empty line, the same
+/// way lrc files mark the end of a line
+fn read_rich_sync(rich_sync_lines: Vec<RichSyncLine>) -> eyre::Result<Lyrics> {
+	let seconds = |secs: f64| Duration::try_from_secs_f64(secs.max(0.0));
+	let mut lines = Vec::new();
+	let mut prev_end: Option<Duration> = None;
+
+	for rich_sync_line in rich_sync_lines {
+		let start = seconds(rich_sync_line.ts)?;
+		let end = seconds(rich_sync_line.te)?;
+		if let Some(prev_end) = prev_end
+			&& prev_end < start
+		{
+			lines.push(LyricLine::new(Some(prev_end.into()), String::new()));
+		}
+
+		let mut text = String::new();
+		let mut words = Vec::new();
+		for piece in &rich_sync_line.l {
+			if !piece.c.trim().is_empty() && !text.ends_with(|c: char| !c.is_whitespace()) {
+				let time = start
+					.checked_add(seconds(piece.o)?)
+					.ok_or_eyre("Value out of range")?;
+				words.push((text.len(), time));
+			}
+			text.push_str(&piece.c);
+		}
+		if rich_sync_line.l.is_empty() {
+			text = rich_sync_line.x.unwrap_or_default();
+		}
+
+		let mut line = LyricLine::new(Some(start.into()), text.trim_end().to_owned());
+		for (idx, time) in words {
+			line.set_word_timestamp(idx, Some(time));
+		}
+		lines.push(line);
+		prev_end = Some(end);
+	}
+	if let Some(prev_end) = prev_end {
+		lines.push(LyricLine::new(Some(prev_end.into()), String::new()));
+	}
+
+	Ok(Lyrics::from_lines(lines))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::lyrics::Timestamp;
+
+	fn lines(lyrics: &Lyrics) -> Vec<(Option<u64>, &str)> {
+		lyrics
+			.lines()
+			.iter()
+			.map(|line| {
+				(
+					line.timestamp().map(|t| t.time().as_millis() as u64),
+					line.text(),
+				)
+			})
+			.collect()
+	}
+
+	fn words(lyrics: &Lyrics, line: usize) -> Vec<(usize, u64)> {
+		lyrics.lines()[line]
+			.word_timestamps()
+			.iter()
+			.map(|(idx, timestamp)| (*idx, timestamp.time().as_millis() as u64))
+			.collect()
+	}
+
+	#[test]
+	fn reads_spotify_lines() {
+		let lyrics = read(
+			r#"{"lyrics": {"syncType": "LINE_SYNCED", "lines": [
+				{"startTimeMs": "1000", "words": "first"},
+				{"startTimeMs": 2500, "words": "♪"},
+				{"startTimeMs": "4000", "words": "second"}
+			]}}"#,
+		)
+		.unwrap();
+		assert_eq!(
+			lines(&lyrics),
+			[
+				(Some(1000), "first"),
+				(Some(2500), ""),
+				(Some(4000), "second")
+			]
+		);
+
+		let lyrics =
+			read(r#"{"syncType": "UNSYNCED", "lines": [{"startTimeMs": "0", "words": "plain"}]}"#)
+				.unwrap();
+		assert_eq!(lines(&lyrics), [(None, "plain")]);
+	}
+
+	#[test]
+	fn reads_rich_sync_lines() {
+		let lyrics = read(
+			r#"[
+				{"ts": 1.0, "te": 2.5, "l": [{"c": "one", "o": 0.0}, {"c": " ", "o": 0.4}, {"c": "two", "o": 0.5}]},
+				{"ts": 4.0, "te": 5.0, "l": [], "x": "no words"}
+			]"#,
+		)
+		.unwrap();
+		assert_eq!(
+			lines(&lyrics),
+			[
+				(Some(1000), "one two"),
+				(Some(2500), ""),
+				(Some(4000), "no words"),
+				(Some(5000), ""),
+			]
+		);
+		assert_eq!(words(&lyrics, 0), [(0, 1000), (4, 1500)]);
+	}
+
+	#[test]
+	fn reads_musixmatch_responses() {
+		let body = r#"[{"ts": 1.0, "te": 2.0, "l": [{"c": "hi", "o": 0.25}]}]"#;
+		let document = serde_json::json!({
+			"message": {"body": {"richsync": {"richsync_body": body}}}
+		});
+		let lyrics = read(&document.to_string()).unwrap();
+		assert_eq!(lines(&lyrics), [(Some(1000), "hi"), (Some(2000), "")]);
+		assert_eq!(
+			lyrics.lines()[0].word_timestamp(0).map(Timestamp::time),
+			Some(Duration::from_millis(1250))
+		);
+	}
+
+	#[test]
+	fn rejects_other_json() {
+		for s in [r#"{"name": "package"}"#, "[1, 2]", "not json"] {
+			let err = read(s).unwrap_err();
+			assert!(
+				err.to_string().starts_with("Unrecognised lyrics json"),
+				"{s}"
+			);
+		}
+	}
+
+	#[test]
+	fn rejects_times_out_of_range() {
+		assert!(read(r#"[{"ts": 1e300, "te": 1.0, "l": []}]"#).is_err());
+		assert!(read(r#"[{"ts": 1.8e19, "te": 1.0, "l": [{"c": "x", "o": 1.8e19}]}]"#).is_err());
+		assert!(read(r#"{"lines": [{"startTimeMs": "-5", "words": "x"}]}"#).is_err());
+	}
+}
@@ -0,0 +1,117 @@
+use std::{
+	io::{BufRead, Write},
+	path::Path,
+	time::Duration,
+};
+
+use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use super::{ass, json, srt, ttml, vtt};
+use crate::lyrics::{Diagnostic, Lyrics, TimestampPrecision};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LyricsFormat {
+	#[default]
+	Lrc,
+	Srt,
+	Vtt,
+	Ttml,
+	Ass,
+	/// lines as exported from streaming services, can only be read
+	Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteOptions<'a> {
+	pub precision: TimestampPrecision,
+	/// length of the track, which the last line may not exceed
+	pub duration: Option<Duration>,
+	/// `[V4+ Styles]` section of .ass files
+	pub ass_styles: &'a str,
+}
+
+impl LyricsFormat {
+	pub fn from_path(path: &Path) -> Option<Self> {
+		match path.extension()?.to_str()? {
+			"lrc" | "txt" => Some(LyricsFormat::Lrc),
+			"srt" => Some(LyricsFormat::Srt),
+			"vtt" => Some(LyricsFormat::Vtt),
+			"ttml" => Some(LyricsFormat::Ttml),
+			"ass" => Some(LyricsFormat::Ass),
+			"json" => Some(LyricsFormat::Json),
+			_ => None,
+		}
+	}
+
+	pub fn extension(self) -> &'static str {
+		match self {
+			LyricsFormat::Lrc => "lrc",
+			LyricsFormat::Srt => "srt",
+			LyricsFormat::Vtt => "vtt",
+			LyricsFormat::Ttml => "ttml",
+			LyricsFormat::Ass => "ass",
+			LyricsFormat::Json => "json",
+		}
+	}
+
+	pub fn can_write(self) -> bool {
+		self != LyricsFormat::Json
+	}
+
+	pub fn read(self, reader: impl BufRead) -> eyre::Result<Lyrics> {
+		self.read_with_diagnostics(reader).map(|(lyrics, _)| lyrics)
+	}
+
+	/// Reads the lyrics along with the malformed parts of the file which could still be read,
+	/// formats other than lrc fail on malformed input instead
+	pub fn read_with_diagnostics(
+		self,
+		mut reader: impl BufRead,
+	) -> eyre::Result<(Lyrics, Vec<Diagnostic>)> {
+		let lyrics = match self {
+			LyricsFormat::Lrc => {
+				let mut lyrics = Lyrics::default();
+				let diagnostics = lyrics.read_overwrite(reader, false)?;
+				return Ok((lyrics, diagnostics));
+			}
+			LyricsFormat::Srt => {
+				let mut s = String::new();
+				reader.read_to_string(&mut s)?;
+				srt::read(&s)
+			}
+			LyricsFormat::Ttml => {
+				let mut s = String::new();
+				reader.read_to_string(&mut s)?;
+				ttml::read(&s)
+			}
+			LyricsFormat::Json => {
+				let mut s = String::new();
+				reader.read_to_string(&mut s)?;
+				json::read(&s)
+			}
+			LyricsFormat::Vtt | LyricsFormat::Ass => Err(eyre::eyre!(
+				"Reading .{} files is not supported",
+				self.extension()
+			)),
+		}?;
+		Ok((lyrics, Vec::new()))
+	}
+
+	pub fn write(
+		self,
+		lyrics: &Lyrics,
+		writer: &mut impl Write,
+		options: &WriteOptions,
+	) -> eyre::Result<()> {
+		match self {
+			LyricsFormat::Lrc => lyrics.write_to(writer, options.precision),
+			LyricsFormat::Srt => srt::write(lyrics, writer, options),
+			LyricsFormat::Vtt => vtt::write(lyrics, writer, options),
+			LyricsFormat::Ttml => ttml::write(lyrics, writer, options),
+			LyricsFormat::Ass => ass::write(lyrics, writer, options),
+			LyricsFormat::Json => Err(eyre::eyre!("Writing .json files is not supported")),
+		}
+	}
+}
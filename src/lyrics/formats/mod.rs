@@ -0,0 +1,11 @@
+mod ass;
+mod cue;
+pub mod embedded;
+mod json;
+mod lyrics_format;
+mod srt;
+mod ttml;
+mod vtt;
+
+pub use ass::DEFAULT_STYLES as DEFAULT_ASS_STYLES;
+pub use lyrics_format::{LyricsFormat, WriteOptions};
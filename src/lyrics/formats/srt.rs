@@ -0,0 +1,176 @@
+use std::{io::Write, time::Duration};
+
+use color_eyre::eyre::{self, OptionExt};
+
+use super::{
+	WriteOptions,
+	cue::{cues, format_time},
+};
+use crate::lyrics::{LyricLine, Lyrics, Timestamp};
+
+/// Reads cues into lines, a gap between the end of a cue and the start of the next is kept as an
+/// empty line, the same way lrc files mark the end of a line
+pub fn read(s: &str) -> eyre::Result<Lyrics> {
+	let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+	let mut lines = Vec::new();
+	let mut prev_end: Option<Duration> = None;
+	let mut rows = s.lines().enumerate().peekable();
+
+	while rows.peek().is_some() {
+		let cue: Vec<_> = rows
+			.by_ref()
+			.skip_while(|(_, row)| row.trim().is_empty())
+			.take_while(|(_, row)| !row.trim().is_empty())
+			.collect();
+		let mut cue = cue.into_iter();
+		let Some((mut row_num, mut row)) = cue.next() else {
+			break;
+		};
+		if !row.contains("-->") {
+			(row_num, row) = cue
+				.next()
+				.ok_or_eyre(format!("Cue without timing on line {}", row_num + 1))?;
+		}
+		let (start, end) = parse_timing(row)
+			.map_err(|e| eyre::eyre!("Invalid cue timing on line {}: {e}", row_num + 1))?;
+		let text = cue.map(|(_, row)| row.trim()).collect::<Vec<_>>().join(" ");
+
+		if let Some(prev_end) = prev_end
+			&& prev_end < start
+		{
+			lines.push(LyricLine::new(Some(prev_end.into()), String::new()));
+		}
+		lines.push(LyricLine::new(Some(start.into()), text));
+		prev_end = Some(end);
+	}
+	if let Some(prev_end) = prev_end {
+		lines.push(LyricLine::new(Some(prev_end.into()), String::new()));
+	}
+
+	Ok(Lyrics::from_lines(lines))
+}
+
+fn parse_timing(s: &str) -> eyre::Result<(Duration, Duration)> {
+	let (start, end) = s.split_once("-->").ok_or_eyre("Missing arrow")?;
+	// cue settings may follow the end time
+	let end = end.split_whitespace().next().unwrap_or_default();
+	Ok((parse_time(start)?, parse_time(end)?))
+}
+
+fn parse_time(s: &str) -> eyre::Result<Duration> {
+	Ok(s.trim().replace(',', ".").parse::<Timestamp>()?.time())
+}
+
+/// Writes every synced line as a cue which lasts until the next synced line starts
+pub fn write(lyrics: &Lyrics, writer: &mut impl Write, options: &WriteOptions) -> eyre::Result<()> {
+	for (i, cue) in cues(lyrics, options).iter().enumerate() {
+		writeln!(writer, "{}", i + 1)?;
+		writeln!(
+			writer,
+			"{} --> {}",
+			format_time(cue.start, ','),
+			format_time(cue.end, ',')
+		)?;
+		writeln!(writer, "{}", cue.line.text().trim())?;
+		writeln!(writer)?;
+	}
+
+	writer.flush()?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::lyrics::TimestampPrecision;
+
+	fn parse_lrc(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	fn write_string(lyrics: &Lyrics, duration: Option<Duration>) -> String {
+		let options = WriteOptions {
+			precision: TimestampPrecision::Keep,
+			duration,
+			ass_styles: "",
+		};
+		let mut out = Vec::new();
+		write(lyrics, &mut out, &options).unwrap();
+		String::from_utf8(out).unwrap()
+	}
+
+	fn lines(lyrics: &Lyrics) -> Vec<(Option<Duration>, &str)> {
+		lyrics
+			.lines()
+			.iter()
+			.map(|line| (line.timestamp().map(Timestamp::time), line.text()))
+			.collect()
+	}
+
+	#[test]
+	fn reads_cues() {
+		let lyrics = read(
+			"\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nfirst\r\nline\r\n\r\n\
+			2\r\n00:00:02,500 --> 00:00:04,000 X1:0\r\nsecond\r\n\r\n\r\n\
+			00:00:05,000 --> 00:00:06,000\r\nwithout number\r\n",
+		)
+		.unwrap();
+		let secs = Duration::from_millis;
+		assert_eq!(
+			lines(&lyrics),
+			[
+				(Some(secs(1000)), "first line"),
+				(Some(secs(2500)), "second"),
+				(Some(secs(4000)), ""),
+				(Some(secs(5000)), "without number"),
+				(Some(secs(6000)), ""),
+			]
+		);
+	}
+
+	#[test]
+	fn reports_the_line_of_a_bad_cue() {
+		let err =
+			read("1\n00:00:01,000 --> 00:00:02,000\nok\n\n2\n00:00:03 --> 00:00:04,000\nbad\n")
+				.unwrap_err();
+		assert!(
+			err.to_string().starts_with("Invalid cue timing on line 6"),
+			"{err}"
+		);
+		let err = read("1\n\n2\nno timing\n").unwrap_err();
+		assert!(err.to_string().contains("line 1"), "{err}");
+	}
+
+	#[test]
+	fn writes_synced_lines_as_cues() {
+		let lyrics = parse_lrc(
+			"[ti:title]\n[offset:+500]\n[00:01.00] first \nunsynced\n[00:03.00]\n\
+			[00:02.00][00:06.00]repeated\n",
+		);
+		assert_eq!(
+			write_string(&lyrics, Some(Duration::from_secs(8))),
+			"1\n00:00:01,500 --> 00:00:02,500\nfirst\n\n\
+			2\n00:00:02,500 --> 00:00:03,500\nrepeated\n\n\
+			3\n00:00:06,500 --> 00:00:08,000\nrepeated\n\n"
+		);
+	}
+
+	#[test]
+	fn last_cue_lasts_five_seconds_without_duration() {
+		let lyrics = parse_lrc("[00:01.00]only\n");
+		assert_eq!(
+			write_string(&lyrics, None),
+			"1\n00:00:01,000 --> 00:00:06,000\nonly\n\n"
+		);
+	}
+
+	#[test]
+	fn round_trips() {
+		let srt = "1\n00:00:01,000 --> 00:00:02,500\nfirst\n\n\
+			2\n00:00:03,000 --> 00:00:04,250\nsecond\n\n";
+		let lyrics = read(srt).unwrap();
+		assert_eq!(write_string(&lyrics, None), srt);
+	}
+}
@@ -0,0 +1,424 @@
+use std::{collections::BTreeSet, io::Write, time::Duration};
+
+use color_eyre::eyre::{self, OptionExt};
+use roxmltree::{Document, Node};
+
+use super::{
+	WriteOptions,
+	cue::{Cue, cues, format_time},
+};
+use crate::lyrics::{LyricLine, Lyrics, TagType, Timestamp, Voice};
+
+const TTML_NS: &str = "http://www.w3.org/ns/ttml";
+const TTM_NS: &str = "http://www.w3.org/ns/ttml#metadata";
+const ITUNES_NS: &str = "http://music.apple.com/lyric-ttml-internal";
+/// agent id apple music uses for lines sung by everyone
+const DUET_AGENT: &str = "v1000";
+
+/// Reads every `<p>` into a line and every timed `<span>` into a word timestamp, a gap between the
+/// end of a paragraph and the start of the next is kept as an empty line
+pub fn read(s: &str) -> eyre::Result<Lyrics> {
+	let document = Document::parse(s)?;
+	let root = document.root_element();
+	if root.tag_name().name() != "tt" {
+		return Err(eyre::eyre!("Not a ttml document"));
+	}
+
+	let agents: Vec<_> = root
+		.descendants()
+		.filter(|node| node.has_tag_name((TTM_NS, "agent")))
+		.collect();
+
+	let mut lines = Vec::new();
+	let mut prev_end: Option<Duration> = None;
+	for p in root.descendants().filter(|node| node.has_tag_name("p")) {
+		let position = document.text_pos_at(p.range().start);
+		let in_paragraph = |e: eyre::Report| {
+			eyre::eyre!(
+				"Invalid paragraph on line {}, column {}: {e}",
+				position.row,
+				position.col
+			)
+		};
+		let start = p
+			.attribute("begin")
+			.ok_or_eyre("Missing begin time")
+			.and_then(parse_time)
+			.map_err(in_paragraph)?;
+		let end = p
+			.attribute("end")
+			.map(parse_time)
+			.transpose()
+			.map_err(in_paragraph)?;
+
+		let mut text = String::new();
+		let mut words = Vec::new();
+		read_text(p, &mut text, &mut words).map_err(in_paragraph)?;
+		let text = text.trim_end().to_owned();
+
+		let voice = p
+			.attribute((TTM_NS, "agent"))
+			.map(|id| agent_voice(id, &agents));
+		let mut line = LyricLine::new(Some(start.into()), text).with_voice(voice);
+		for (idx, time) in words {
+			if idx < line.text().len() {
+				line.set_word_timestamp(idx, Some(time));
+			}
+		}
+
+		if let Some(prev_end) = prev_end
+			&& prev_end < start
+		{
+			lines.push(LyricLine::new(Some(prev_end.into()), String::new()));
+		}
+		lines.push(line);
+		prev_end = end;
+	}
+	if let Some(prev_end) = prev_end {
+		lines.push(LyricLine::new(Some(prev_end.into()), String::new()));
+	}
+
+	let mut lyrics = Lyrics::from_lines(lines);
+	if let Some(title) = root
+		.descendants()
+		.find(|node| node.has_tag_name((TTM_NS, "title")))
+		.and_then(|node| node.text())
+	{
+		lyrics.set_tag(TagType::Title, Some(title.trim().to_owned()));
+	}
+	Ok(lyrics)
+}
+
+/// Maps an agent id to a voice, group agents are sung as a duet and agents with an id that is not a
+/// voice are numbered in the order they are declared
+fn agent_voice(id: &str, agents: &[Node]) -> Voice {
+	let declared = agents
+		.iter()
+		.position(|agent| agent.attribute((roxmltree::NS_XML_URI, "id")) == Some(id));
+	let is_group = declared.is_some_and(|i| agents[i].attribute("type") == Some("group"));
+	if is_group || id == DUET_AGENT {
+		return Voice::Duet;
+	}
+	id.parse().unwrap_or_else(|_| {
+		let number = declared.unwrap_or(agents.len()) + 1;
+		Voice::Numbered(number.try_into().unwrap_or(u16::MAX))
+	})
+}
+
+fn agent_id(voice: Voice) -> String {
+	match voice {
+		Voice::Duet => DUET_AGENT.to_owned(),
+		voice => voice.to_string(),
+	}
+}
+
+/// Appends the text in `node` with runs of whitespace collapsed, and the start of every timed span
+fn read_text(
+	node: Node,
+	text: &mut String,
+	words: &mut Vec<(usize, Duration)>,
+) -> eyre::Result<()> {
+	for child in node.children() {
+		if let Some(s) = child.text().filter(|_| child.is_text()) {
+			for c in s.chars() {
+				if !c.is_whitespace() {
+					text.push(c);
+				} else if !text.is_empty() && !text.ends_with(' ') {
+					text.push(' ');
+				}
+			}
+		} else if child.has_tag_name("br") {
+			if !text.is_empty() && !text.ends_with(' ') {
+				text.push(' ');
+			}
+		} else if child.has_tag_name("span") {
+			if let Some(begin) = child.attribute("begin") {
+				words.push((text.len(), parse_time(begin)?));
+			}
+			read_text(child, text, words)?;
+		}
+	}
+	Ok(())
+}
+
+/// Parses a clock time like `00:01:02.345` or `01:02.345`, or an offset time like `62.345s`
+fn parse_time(s: &str) -> eyre::Result<Duration> {
+	let s = s.trim();
+	if let Some(millis) = s.strip_suffix("ms") {
+		Ok(Duration::try_from_secs_f64(
+			millis.parse::<f64>()? / 1000.0,
+		)?)
+	} else if let Some(secs) = s.strip_suffix('s') {
+		Ok(Duration::try_from_secs_f64(secs.parse()?)?)
+	} else if s.contains(':') {
+		let (clock, fraction) = s.split_once('.').unwrap_or((s, "0"));
+		let fraction = fraction.get(..3).unwrap_or(fraction);
+		Ok(format!("{clock}.{fraction}").parse::<Timestamp>()?.time())
+	} else {
+		Ok(Duration::try_from_secs_f64(s.parse()?)?)
+	}
+}
+
+/// Writes every synced line as a paragraph, with a span for every word that has a timestamp
+pub fn write(lyrics: &Lyrics, writer: &mut impl Write, options: &WriteOptions) -> eyre::Result<()> {
+	let cues = cues(lyrics, options);
+	let voices: BTreeSet<_> = cues.iter().filter_map(|cue| cue.line.voice()).collect();
+	let timing = if cues
+		.iter()
+		.any(|cue| !cue.line.word_timestamps().is_empty())
+	{
+		"Word"
+	} else {
+		"Line"
+	};
+
+	writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+	writeln!(
+		writer,
+		r#"<tt xmlns="{TTML_NS}" xmlns:ttm="{TTM_NS}" xmlns:itunes="{ITUNES_NS}" itunes:timing="{timing}">"#
+	)?;
+	writeln!(writer, "  <head>")?;
+	writeln!(writer, "    <metadata>")?;
+	if let Some(title) = lyrics.tag(&TagType::Title) {
+		writeln!(writer, "      <ttm:title>{}</ttm:title>", escape(title))?;
+	}
+	for voice in voices {
+		let kind = match voice {
+			Voice::Duet => "group",
+			_ => "person",
+		};
+		writeln!(
+			writer,
+			r#"      <ttm:agent type="{kind}" xml:id="{}"/>"#,
+			agent_id(voice)
+		)?;
+	}
+	writeln!(writer, "    </metadata>")?;
+	writeln!(writer, "  </head>")?;
+
+	match options.duration {
+		Some(duration) => writeln!(writer, r#"  <body dur="{}">"#, format_time(duration, '.'))?,
+		None => writeln!(writer, "  <body>")?,
+	}
+	if let (Some(first), Some(last)) = (cues.first(), cues.last()) {
+		writeln!(
+			writer,
+			r#"    <div begin="{}" end="{}">"#,
+			format_time(first.start, '.'),
+			format_time(last.end, '.')
+		)?;
+		for cue in &cues {
+			write!(
+				writer,
+				r#"      <p begin="{}" end="{}""#,
+				format_time(cue.start, '.'),
+				format_time(cue.end, '.')
+			)?;
+			if let Some(voice) = cue.line.voice() {
+				write!(writer, r#" ttm:agent="{}""#, agent_id(voice))?;
+			}
+			writeln!(writer, ">{}</p>", paragraph_content(cue))?;
+		}
+		writeln!(writer, "    </div>")?;
+	}
+	writeln!(writer, "  </body>")?;
+	writeln!(writer, "</tt>")?;
+
+	writer.flush()?;
+	Ok(())
+}
+
+/// Line text with every timed word wrapped in a span which lasts until the next word starts, word
+/// timestamps past the end of the trimmed text only end the word before them
+fn paragraph_content(cue: &Cue) -> String {
+	let text = cue.line.text().trim_end();
+	let words = cue.line.word_timestamps();
+	let Some((first, _)) = words.first() else {
+		return escape(text);
+	};
+
+	let mut s = escape(&text[..(*first).min(text.len())]);
+	for (i, (idx, timestamp)) in words.iter().enumerate() {
+		if *idx >= text.len() {
+			break;
+		}
+		let (word_end, end) = match words.get(i + 1) {
+			Some((next_idx, next)) => ((*next_idx).min(text.len()), next.time()),
+			None => (text.len(), cue.end),
+		};
+		let word = &text[(*idx).min(word_end)..word_end];
+		let trimmed = word.trim_end();
+		s.push_str(&format!(
+			r#"<span begin="{}" end="{}">{}</span>{}"#,
+			format_time(timestamp.time(), '.'),
+			format_time(end.max(timestamp.time()), '.'),
+			escape(trimmed),
+			escape(&word[trimmed.len()..])
+		));
+	}
+	s
+}
+
+fn escape(s: &str) -> String {
+	s.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::lyrics::TimestampPrecision;
+
+	fn parse(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	fn write_string(lyrics: &Lyrics) -> String {
+		let options = WriteOptions {
+			precision: TimestampPrecision::Keep,
+			duration: Some(Duration::from_secs(10)),
+			ass_styles: "",
+		};
+		let mut out = Vec::new();
+		write(lyrics, &mut out, &options).unwrap();
+		String::from_utf8(out).unwrap()
+	}
+
+	fn paragraphs(s: &str) -> Vec<&str> {
+		s.lines()
+			.map(str::trim)
+			.filter(|line| line.starts_with("<p "))
+			.collect()
+	}
+
+	#[test]
+	fn reads_paragraphs_spans_and_agents() {
+		let lyrics = read(
+			r#"<tt xmlns="http://www.w3.org/ns/ttml" xmlns:ttm="http://www.w3.org/ns/ttml#metadata">
+				<head><metadata>
+					<ttm:title>Song &amp; more</ttm:title>
+					<ttm:agent type="person" xml:id="singer"/>
+					<ttm:agent type="group" xml:id="all"/>
+				</metadata></head>
+				<body><div>
+					<p begin="1.5s" end="00:00:03.000" ttm:agent="singer">
+						<span begin="1.5s">one</span> <span begin="00:02.25">two</span>
+					</p>
+					<p begin="4s" end="5s" ttm:agent="all">together<br/>again</p>
+				</div></body>
+			</tt>"#,
+		)
+		.unwrap();
+		assert_eq!(lyrics.tag(&TagType::Title), Some("Song & more"));
+		let lines: Vec<_> = lyrics
+			.lines()
+			.iter()
+			.map(|line| {
+				(
+					line.timestamp().map(Timestamp::time),
+					line.text(),
+					line.voice(),
+				)
+			})
+			.collect();
+		assert_eq!(
+			lines,
+			[
+				(
+					Some(Duration::from_millis(1500)),
+					"one two",
+					Some(Voice::Numbered(1))
+				),
+				(Some(Duration::from_secs(3)), "", None),
+				(
+					Some(Duration::from_secs(4)),
+					"together again",
+					Some(Voice::Duet)
+				),
+				(Some(Duration::from_secs(5)), "", None),
+			]
+		);
+		let words: Vec<_> = lyrics.lines()[0]
+			.word_timestamps()
+			.iter()
+			.map(|(idx, timestamp)| (*idx, timestamp.time()))
+			.collect();
+		assert_eq!(
+			words,
+			[
+				(0, Duration::from_millis(1500)),
+				(4, Duration::from_millis(2250))
+			]
+		);
+	}
+
+	#[test]
+	fn rejects_invalid_times() {
+		for time in ["-1s", "NaNs", "1e400s", "abc", "01:60.000"] {
+			let s = format!(
+				r#"<tt xmlns="http://www.w3.org/ns/ttml"><body><p begin="{time}">x</p></body></tt>"#
+			);
+			assert!(read(&s).is_err(), "{time}");
+		}
+	}
+
+	#[test]
+	fn round_trips_words_and_voices() {
+		let lyrics =
+			parse("[ti:a <b>]\n[00:01.00]v1: <00:01.00>one <00:01.50>two\n[00:03.00]D: all\n");
+		let written = write_string(&lyrics);
+		assert!(written.contains("<ttm:title>a &lt;b&gt;</ttm:title>"));
+		assert_eq!(
+			paragraphs(&written),
+			[
+				r#"<p begin="00:00:01.000" end="00:00:03.000" ttm:agent="v1"><span begin="00:00:01.000" end="00:00:01.500">one</span> <span begin="00:00:01.500" end="00:00:03.000">two</span></p>"#,
+				r#"<p begin="00:00:03.000" end="00:00:10.000" ttm:agent="v1000">all</p>"#,
+			]
+		);
+
+		let read_back = read(&written).unwrap();
+		assert_eq!(read_back.lines()[0].text(), "one two");
+		assert_eq!(
+			read_back.lines()[0].word_timestamps(),
+			lyrics.lines()[0].word_timestamps()
+		);
+		assert_eq!(read_back.lines()[0].voice(), Some(Voice::Numbered(1)));
+		assert_eq!(read_back.lines()[1].voice(), Some(Voice::Duet));
+	}
+
+	#[test]
+	fn trailing_word_timestamp_ends_the_last_word() {
+		let lyrics = parse("[00:01.00]<00:01.00>hello <00:01.50>world <00:02.00>\n");
+		assert_eq!(
+			paragraphs(&write_string(&lyrics)),
+			[
+				r#"<p begin="00:00:01.000" end="00:00:10.000"><span begin="00:00:01.000" end="00:00:01.500">hello</span> <span begin="00:00:01.500" end="00:00:02.000">world</span></p>"#
+			]
+		);
+	}
+
+	#[test]
+	fn ignores_word_timestamps_past_the_text() {
+		let mut lyrics = parse("[00:01.00]short\n");
+		let words = ["00:01.00", "00:01.50", "00:02.00"].map(|s| s.parse::<Timestamp>().unwrap());
+		lyrics.set_text_at_line(
+			0,
+			"short ".to_owned(),
+			vec![
+				(0, words[0].clone()),
+				(40, words[1].clone()),
+				(80, words[2].clone()),
+			],
+		);
+		assert_eq!(
+			paragraphs(&write_string(&lyrics)),
+			[
+				r#"<p begin="00:00:01.000" end="00:00:10.000"><span begin="00:00:01.000" end="00:00:01.500">short</span></p>"#
+			]
+		);
+	}
+}
@@ -0,0 +1,123 @@
+use std::io::Write;
+
+use color_eyre::eyre;
+
+use super::{
+	WriteOptions,
+	cue::{Cue, cues, format_time},
+};
+use crate::lyrics::{Lyrics, TagType};
+
+/// Writes every synced line as a cue, with word timestamps as karaoke style inline timestamps
+pub fn write(lyrics: &Lyrics, writer: &mut impl Write, options: &WriteOptions) -> eyre::Result<()> {
+	writeln!(writer, "WEBVTT")?;
+
+	let notes: Vec<_> = lyrics
+		.metadata()
+		.iter()
+		.filter_map(|meta| match meta.tag_type() {
+			TagType::Title => Some(format!("Title: {}", meta.value())),
+			TagType::Artist => Some(format!("Artist: {}", meta.value())),
+			_ => None,
+		})
+		.collect();
+	if !notes.is_empty() {
+		writeln!(writer)?;
+		writeln!(writer, "NOTE")?;
+		for note in notes {
+			// a note ends at the first blank line and may not contain an arrow
+			writeln!(writer, "{}", note.replace("-->", "->"))?;
+		}
+	}
+
+	for (i, cue) in cues(lyrics, options).iter().enumerate() {
+		writeln!(writer)?;
+		writeln!(writer, "{}", i + 1)?;
+		writeln!(
+			writer,
+			"{} --> {}",
+			format_time(cue.start, '.'),
+			format_time(cue.end, '.')
+		)?;
+		writeln!(writer, "{}", cue_text(cue))?;
+	}
+
+	writer.flush()?;
+	Ok(())
+}
+
+/// Line text with the word timestamps which fall inside the cue as inline timestamps, and a voice
+/// span for the singer
+fn cue_text(cue: &Cue) -> String {
+	let text = cue.line.text();
+	let mut s = String::with_capacity(text.len());
+	if let Some(voice) = cue.line.voice() {
+		s.push_str(&format!("<v {voice}>"));
+	}
+	let mut prev = 0;
+	for (idx, timestamp) in cue.line.word_timestamps() {
+		let time = timestamp.time();
+		if time <= cue.start || time >= cue.end {
+			continue;
+		}
+		s.push_str(&escape(&text[prev..*idx]));
+		s.push('<');
+		s.push_str(&format_time(time, '.'));
+		s.push('>');
+		prev = *idx;
+	}
+	s.push_str(&escape(&text[prev..]));
+	s.trim().to_owned()
+}
+
+fn escape(s: &str) -> String {
+	s.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::lyrics::TimestampPrecision;
+
+	fn write_string(s: &str) -> String {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		let options = WriteOptions {
+			precision: TimestampPrecision::Keep,
+			duration: Some(Duration::from_secs(10)),
+			ass_styles: "",
+		};
+		let mut out = Vec::new();
+		write(&lyrics, &mut out, &options).unwrap();
+		String::from_utf8(out).unwrap()
+	}
+
+	#[test]
+	fn writes_header_notes_and_cues() {
+		assert_eq!(
+			write_string(
+				"[ti:a --> b]\n[ar:someone]\n[al:album]\n[00:01.00]first\n[00:02.00]<b> & </b>\n"
+			),
+			"WEBVTT\n\nNOTE\nTitle: a -> b\nArtist: someone\n\n\
+			1\n00:00:01.000 --> 00:00:02.000\nfirst\n\n\
+			2\n00:00:02.000 --> 00:00:10.000\n&lt;b&gt; &amp; &lt;/b&gt;\n"
+		);
+	}
+
+	#[test]
+	fn writes_voices_and_word_timestamps_inside_the_cue() {
+		assert_eq!(
+			write_string(
+				"[00:01.00]v2: <00:01.00>one <00:01.50>two <00:03.00>\n\
+				[00:02.00]<00:01.00>early <00:02.50>on <00:12.00>late\n"
+			),
+			"WEBVTT\n\n\
+			1\n00:00:01.000 --> 00:00:02.000\n<v v2>one <00:00:01.500>two\n\n\
+			2\n00:00:02.000 --> 00:00:10.000\nearly <00:00:02.500>on late\n"
+		);
+	}
+}
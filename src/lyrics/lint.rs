@@ -0,0 +1,392 @@
+use std::{fmt, time::Duration};
+
+use super::{Lyrics, TagType};
+
+/// Lines shorter than this were most likely synced by accident
+const MIN_LINE_DURATION: Duration = Duration::from_millis(300);
+/// How far the `[length:]` tag may be off from the length of the audio file
+const LENGTH_TOLERANCE: Duration = Duration::from_secs(1);
+
+/// What is known about the audio file the lyrics are checked against
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrackInfo {
+	pub duration: Option<Duration>,
+	pub title: Option<String>,
+	pub artist: Option<String>,
+	pub album: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProblemKind {
+	OutOfOrder,
+	WordOutOfOrder,
+	BeyondDuration(Duration),
+	DuplicateTimestamp { line: u16 },
+	UnsyncedLine,
+	ShortLine(Duration),
+	TrailingWhitespace,
+	TagMismatch { tag: TagType, expected: String },
+}
+
+/// A problem found in the lyrics, at the line and display column the cursor should jump to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+	pub kind: ProblemKind,
+	pub line: u16,
+	pub x: u16,
+}
+
+impl Problem {
+	fn new(kind: ProblemKind, line: usize, x: u16) -> Self {
+		Self {
+			kind,
+			line: line as u16,
+			x,
+		}
+	}
+
+	/// Whether the problem can be fixed without a chance of making the lyrics worse
+	pub fn is_safe_to_fix(&self) -> bool {
+		matches!(
+			self.kind,
+			ProblemKind::TrailingWhitespace
+				| ProblemKind::TagMismatch {
+					tag: TagType::Length,
+					..
+				}
+		)
+	}
+}
+
+impl fmt::Display for Problem {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.kind {
+			ProblemKind::OutOfOrder => f.write_str("Timestamp is earlier than the previous line"),
+			ProblemKind::WordOutOfOrder => {
+				f.write_str("Word timestamp is earlier than the previous word")
+			}
+			ProblemKind::BeyondDuration(duration) => write!(
+				f,
+				"Timestamp is past the end of the track at {}",
+				format_length(*duration)
+			),
+			ProblemKind::DuplicateTimestamp { line } => {
+				write!(f, "Same timestamp as line {}", line + 1)
+			}
+			ProblemKind::UnsyncedLine => f.write_str("Line between synced lines is not synced"),
+			ProblemKind::ShortLine(duration) => {
+				write!(f, "Line only lasts {:.2} seconds", duration.as_secs_f32())
+			}
+			ProblemKind::TrailingWhitespace => f.write_str("Trailing whitespace"),
+			ProblemKind::TagMismatch { tag, expected } => write!(
+				f,
+				"[{}:] tag does not match the audio file, which has {expected}",
+				tag.key()
+			),
+		}
+	}
+}
+
+/// Finds the problems in the lyrics, sorted by line
+pub fn check(lyrics: &Lyrics, track: &TrackInfo) -> Vec<Problem> {
+	let lines = lyrics.lines();
+	let first_synced = lines.iter().position(|line| line.timestamp().is_some());
+	let last_synced = lines.iter().rposition(|line| line.timestamp().is_some());
+	let mut problems = Vec::new();
+	let mut prev: Option<(usize, Duration)> = None;
+
+	for (i, line) in lines.iter().enumerate() {
+		let text = line.text();
+		// enhanced lrc puts a space before the timestamp marking the end of the last word
+		let ends_with_word_timestamp = line
+			.word_timestamps()
+			.last()
+			.is_some_and(|(idx, _)| *idx == text.len());
+		if text.ends_with(char::is_whitespace) && !ends_with_word_timestamp {
+			let x = line.column_at(text.trim_end().len());
+			problems.push(Problem::new(ProblemKind::TrailingWhitespace, i, x));
+		}
+
+		let Some(timestamp) = line.timestamp() else {
+			if !text.trim().is_empty()
+				&& first_synced.is_some_and(|first| first < i)
+				&& last_synced.is_some_and(|last| i < last)
+			{
+				problems.push(Problem::new(ProblemKind::UnsyncedLine, i, 0));
+			}
+			continue;
+		};
+		let time = timestamp.time();
+
+		match prev {
+			Some((_, prev_time)) if time < prev_time => {
+				problems.push(Problem::new(ProblemKind::OutOfOrder, i, 0));
+			}
+			Some((prev_line, prev_time)) if time == prev_time => {
+				let kind = ProblemKind::DuplicateTimestamp {
+					line: prev_line as u16,
+				};
+				problems.push(Problem::new(kind, i, 0));
+			}
+			_ => (),
+		}
+		prev = Some((i, time));
+
+		let mut prev_word = time;
+		for (idx, word_timestamp) in line.word_timestamps() {
+			if word_timestamp.time() < prev_word {
+				let x = line.column_at(*idx);
+				problems.push(Problem::new(ProblemKind::WordOutOfOrder, i, x));
+			}
+			prev_word = word_timestamp.time();
+		}
+
+		if let Some(duration) = track.duration {
+			let beyond = |time: Duration| lyrics.apply_offset(time) > duration;
+			let word_beyond = line
+				.word_timestamps()
+				.iter()
+				.find(|(_, timestamp)| beyond(timestamp.time()));
+			if beyond(time)
+				|| line
+					.repeat_timestamps()
+					.iter()
+					.any(|timestamp| beyond(timestamp.time()))
+			{
+				problems.push(Problem::new(ProblemKind::BeyondDuration(duration), i, 0));
+			} else if let Some((idx, _)) = word_beyond {
+				let x = line.column_at(*idx);
+				problems.push(Problem::new(ProblemKind::BeyondDuration(duration), i, x));
+			}
+		}
+
+		if !text.trim().is_empty()
+			&& let Some(next_time) = lines[i + 1..]
+				.iter()
+				.find_map(|line| line.timestamp())
+				.map(|timestamp| timestamp.time())
+			&& next_time > time
+			&& next_time - time < MIN_LINE_DURATION
+		{
+			problems.push(Problem::new(ProblemKind::ShortLine(next_time - time), i, 0));
+		}
+	}
+
+	check_tags(lyrics, track, &mut problems);
+	problems.sort_by_key(|problem| (problem.line, problem.x));
+	problems
+}
+
+fn check_tags(lyrics: &Lyrics, track: &TrackInfo, problems: &mut Vec<Problem>) {
+	let tag_line = |tag_type: &TagType| {
+		let line_num = lyrics
+			.metadata()
+			.iter()
+			.rev()
+			.find(|meta| meta.tag_type() == tag_type)
+			.map_or(0, |meta| meta.line_num());
+		line_num.min(lyrics.lines().len() - 1)
+	};
+
+	if let (Some(duration), Some(length)) = (track.duration, lyrics.tag(&TagType::Length))
+		&& parse_length(length).is_none_or(|length| length.abs_diff(duration) > LENGTH_TOLERANCE)
+	{
+		let kind = ProblemKind::TagMismatch {
+			tag: TagType::Length,
+			expected: format_length(duration),
+		};
+		problems.push(Problem::new(kind, tag_line(&TagType::Length), 0));
+	}
+
+	for (tag, expected) in [
+		(TagType::Title, &track.title),
+		(TagType::Artist, &track.artist),
+	] {
+		if let (Some(value), Some(expected)) = (lyrics.tag(&tag), expected)
+			&& !expected.trim().is_empty()
+			&& !value.trim().eq_ignore_ascii_case(expected.trim())
+		{
+			let line = tag_line(&tag);
+			let kind = ProblemKind::TagMismatch {
+				tag,
+				expected: expected.trim().to_owned(),
+			};
+			problems.push(Problem::new(kind, line, 0));
+		}
+	}
+}
+
+/// Fixes the problems for which [`Problem::is_safe_to_fix`] holds
+pub fn fix_safe(lyrics: &mut Lyrics, track: &TrackInfo) {
+	for problem in check(lyrics, track) {
+		if !problem.is_safe_to_fix() {
+			continue;
+		}
+		match problem.kind {
+			ProblemKind::TrailingWhitespace => lyrics.trim_line_end(problem.line as usize),
+			ProblemKind::TagMismatch { tag, expected } => lyrics.set_tag(tag, Some(expected)),
+			_ => (),
+		}
+	}
+}
+
+/// Parses the value of a `[length:]` tag, like `03:25` or `3:25.50`
+fn parse_length(s: &str) -> Option<Duration> {
+	let (minutes, seconds) = s.trim().split_once(':')?;
+	let minutes: u64 = minutes.trim().parse().ok()?;
+	let seconds: f64 = seconds.trim().parse().ok()?;
+	if !(0.0..60.0).contains(&seconds) {
+		return None;
+	}
+	Duration::from_secs(minutes.checked_mul(60)?).checked_add(Duration::from_secs_f64(seconds))
+}
+
+/// Formats a duration the way `[length:]` tags are written, like `03:25`
+pub fn format_length(duration: Duration) -> String {
+	let secs = duration.as_secs();
+	format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	fn problems(s: &str, track: &TrackInfo) -> Vec<(ProblemKind, u16, u16)> {
+		check(&parse(s), track)
+			.into_iter()
+			.map(|problem| (problem.kind, problem.line, problem.x))
+			.collect()
+	}
+
+	#[test]
+	fn finds_timing_problems() {
+		let lrc = "[00:02.00]a\n[00:01.00]b\n[00:01.00]c\nunsynced\n[00:05.00]d\n[00:05.10]e\n\n[00:08.00]f\n";
+		assert_eq!(
+			problems(lrc, &TrackInfo::default()),
+			[
+				(ProblemKind::OutOfOrder, 1, 0),
+				(ProblemKind::DuplicateTimestamp { line: 1 }, 2, 0),
+				(ProblemKind::UnsyncedLine, 3, 0),
+				(ProblemKind::ShortLine(Duration::from_millis(100)), 4, 0),
+			]
+		);
+	}
+
+	#[test]
+	fn unsynced_lines_outside_synced_ones_are_fine() {
+		let lrc = "intro\n[00:01.00]a\n[00:02.00]b\noutro\n";
+		assert!(problems(lrc, &TrackInfo::default()).is_empty());
+	}
+
+	#[test]
+	fn finds_word_problems() {
+		let lrc = "[00:01.00]<00:01.00>one <00:00.50>two \n[00:03.00]<00:03.00>end <00:04.00>\n";
+		assert_eq!(
+			problems(lrc, &TrackInfo::default()),
+			[
+				(ProblemKind::WordOutOfOrder, 0, 4),
+				(ProblemKind::TrailingWhitespace, 0, 7),
+			]
+		);
+	}
+
+	#[test]
+	fn finds_timestamps_beyond_duration() {
+		let duration = Duration::from_secs(10);
+		let track = TrackInfo {
+			duration: Some(duration),
+			..TrackInfo::default()
+		};
+		let lrc = "[00:05.00]ok <00:11.00>x\n[00:06.00][00:15.00]repeat\n[00:12.00]late\n";
+		assert_eq!(
+			problems(lrc, &track),
+			[
+				(ProblemKind::BeyondDuration(duration), 0, 3),
+				(ProblemKind::BeyondDuration(duration), 1, 0),
+				(ProblemKind::BeyondDuration(duration), 2, 0),
+			]
+		);
+		// the offset moves the lines back into the track
+		let lrc = "[offset:-3000]\n[00:12.00]early\n";
+		assert!(problems(lrc, &track).is_empty());
+	}
+
+	#[test]
+	fn finds_tags_not_matching_the_track() {
+		let track = TrackInfo {
+			duration: Some(Duration::from_millis(181_500)),
+			title: Some("Song".to_owned()),
+			artist: Some("artist".to_owned()),
+			album: None,
+		};
+		let lrc = "[ti:Other]\n[ar: ARTIST ]\n[length:03:00]\n[00:01.00]a\n";
+		assert_eq!(
+			problems(lrc, &track),
+			[
+				(
+					ProblemKind::TagMismatch {
+						tag: TagType::Length,
+						expected: "03:01".to_owned()
+					},
+					0,
+					0
+				),
+				(
+					ProblemKind::TagMismatch {
+						tag: TagType::Title,
+						expected: "Song".to_owned()
+					},
+					0,
+					0
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn fixes_only_safe_problems() {
+		let track = TrackInfo {
+			duration: Some(Duration::from_secs(200)),
+			title: Some("Song".to_owned()),
+			..TrackInfo::default()
+		};
+		let mut lyrics = parse("[ti:Other]\n[length:01:00]\n[00:02.00]a  \n[00:01.00]b\n");
+		fix_safe(&mut lyrics, &track);
+
+		assert_eq!(lyrics.tag(&TagType::Length), Some("03:20"));
+		assert_eq!(lyrics.tag(&TagType::Title), Some("Other"));
+		assert_eq!(lyrics.lines()[0].text(), "a");
+		let remaining: Vec<_> = check(&lyrics, &track)
+			.into_iter()
+			.map(|problem| problem.kind)
+			.collect();
+		assert_eq!(remaining.len(), 2);
+		assert!(matches!(remaining[0], ProblemKind::TagMismatch { .. }));
+		assert_eq!(remaining[1], ProblemKind::OutOfOrder);
+	}
+
+	#[test]
+	fn parses_length_tags() {
+		assert_eq!(parse_length("03:25"), Some(Duration::from_secs(205)));
+		assert_eq!(
+			parse_length(" 3:25.50 "),
+			Some(Duration::from_millis(205_500))
+		);
+		assert_eq!(parse_length("125:00"), Some(Duration::from_secs(7500)));
+		assert_eq!(parse_length("03:60"), None);
+		assert_eq!(parse_length("3.25"), None);
+		assert_eq!(parse_length("-1:00"), None);
+	}
+
+	#[test]
+	fn huge_length_is_not_a_length() {
+		assert_eq!(parse_length("18446744073709551615:00"), None);
+		assert_eq!(parse_length("307445734561825861:00"), None);
+	}
+}
@@ -1,21 +1,98 @@
-use super::Timestamp;
+use std::{ops::Range, time::Duration};
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{Timestamp, TimestampPrecision, Voice};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LyricLine {
 	timestamp: Option<Timestamp>,
 	text: String,
+	/// further timestamps at which the same line is repeated
+	repeat_timestamps: Vec<Timestamp>,
+	/// inline word timestamps as (byte index into text, timestamp), sorted by index
+	word_timestamps: Vec<(usize, Timestamp)>,
+	/// singer of this line, written as a prefix to the text
+	voice: Option<Voice>,
+	/// translations and romanisations as (language, line), the language is that of the
+	/// `song.<lang>.lrc` file the line was read from, or `None` if it follows this line in the same
+	/// file with the same timestamp
+	secondary_lines: Vec<(Option<String>, LyricLine)>,
+	/// the line as it was read, written back as long as the line is unchanged
+	source: Option<String>,
 }
 
 impl LyricLine {
 	pub fn new(timestamp: Option<Timestamp>, text: String) -> Self {
-		Self { timestamp, text }
+		Self {
+			timestamp,
+			text,
+			repeat_timestamps: Vec::new(),
+			word_timestamps: Vec::new(),
+			voice: None,
+			secondary_lines: Vec::new(),
+			source: None,
+		}
+	}
+
+	pub fn with_source(mut self, source: &str) -> Self {
+		self.source = Some(source.to_owned());
+		self
+	}
+
+	/// The source line, as long as none of its timestamps would be written differently
+	/// at the given precision
+	pub fn source_with_precision(&self, precision: TimestampPrecision) -> Option<&str> {
+		let source = self.source.as_deref()?;
+		self.timestamp
+			.iter()
+			.chain(&self.repeat_timestamps)
+			.chain(self.word_timestamps.iter().map(|(_, timestamp)| timestamp))
+			.all(|timestamp| timestamp.format(precision) == timestamp.text())
+			.then_some(source)
+	}
+
+	/// Parses text which may start with a voice prefix and contain enhanced lrc inline timestamps,
+	/// e.g. `v1: <00:12.50>word`
+	pub fn parse_enhanced(timestamp: Option<Timestamp>, s: &str) -> Self {
+		let (voice, mut rest) = Voice::strip_prefix(s);
+		let mut line = Self::new(timestamp, String::with_capacity(rest.len())).with_voice(voice);
+
+		while let Some(open) = rest.find('<') {
+			let Some(close) = rest[open..].find('>').map(|i| open + i) else {
+				break;
+			};
+			let Ok(word_timestamp) = rest[open + 1..close].parse() else {
+				line.text.push_str(&rest[..close + 1]);
+				rest = &rest[close + 1..];
+				continue;
+			};
+
+			line.text.push_str(&rest[..open]);
+			rest = &rest[close + 1..];
+			line.word_timestamps.push((line.text.len(), word_timestamp));
+		}
+		line.text.push_str(rest);
+
+		line
 	}
 
 	pub fn set_timestamp<T>(&mut self, timestamp: Option<T>)
 	where
 		T: Into<Timestamp>,
 	{
-		self.timestamp = timestamp.map(|x| x.into());
+		let timestamp = timestamp.map(|x| x.into());
+		self.timestamp = match (&self.timestamp, timestamp) {
+			(Some(prev), Some(timestamp)) => Some(prev.with_time(timestamp.time())),
+			(_, timestamp) => timestamp,
+		};
+		self.source = None;
+
+		let time = self.timestamp.as_ref().map(Timestamp::time);
+		for (_, line) in &mut self.secondary_lines {
+			line.set_timestamp(time);
+		}
 	}
 
 	pub fn timestamp(&self) -> Option<&Timestamp> {
@@ -29,7 +106,304 @@ impl LyricLine {
 		}
 	}
 
+	pub fn with_repeat_timestamps(mut self, repeat_timestamps: Vec<Timestamp>) -> Self {
+		self.repeat_timestamps = repeat_timestamps;
+		self
+	}
+
+	pub fn repeat_timestamps(&self) -> &[Timestamp] {
+		&self.repeat_timestamps
+	}
+
+	pub fn set_repeat_timestamps(&mut self, repeat_timestamps: Vec<Timestamp>) {
+		self.repeat_timestamps = repeat_timestamps;
+		self.source = None;
+	}
+
+	pub fn take_repeat_timestamps(&mut self) -> Vec<Timestamp> {
+		if !self.repeat_timestamps.is_empty() {
+			self.source = None;
+		}
+		std::mem::take(&mut self.repeat_timestamps)
+	}
+
+	pub fn with_voice(mut self, voice: Option<Voice>) -> Self {
+		self.voice = voice;
+		self
+	}
+
+	pub fn voice(&self) -> Option<Voice> {
+		self.voice
+	}
+
+	pub fn set_voice(&mut self, voice: Option<Voice>) {
+		if self.voice != voice {
+			self.voice = voice;
+			self.source = None;
+		}
+	}
+
+	pub fn secondary_lines(&self) -> &[(Option<String>, LyricLine)] {
+		&self.secondary_lines
+	}
+
+	pub fn push_secondary_line(&mut self, lang: Option<String>, line: LyricLine) {
+		self.secondary_lines.push((lang, line));
+	}
+
+	pub fn has_secondary_line(&self, lang: Option<&str>) -> bool {
+		self.secondary_lines
+			.iter()
+			.any(|(secondary_lang, _)| secondary_lang.as_deref() == lang)
+	}
+
 	pub fn text(&self) -> &str {
 		&self.text
 	}
+
+	pub fn word_timestamps(&self) -> &[(usize, Timestamp)] {
+		&self.word_timestamps
+	}
+
+	pub fn word_timestamp(&self, word: usize) -> Option<&Timestamp> {
+		self.word_timestamps
+			.binary_search_by_key(&word, |(idx, _)| *idx)
+			.ok()
+			.map(|i| &self.word_timestamps[i].1)
+	}
+
+	pub fn set_word_timestamp<T>(&mut self, word: usize, timestamp: Option<T>)
+	where
+		T: Into<Timestamp>,
+	{
+		let pos = self
+			.word_timestamps
+			.binary_search_by_key(&word, |(idx, _)| *idx);
+		match (pos, timestamp) {
+			(Ok(i), Some(timestamp)) => {
+				let prev = &self.word_timestamps[i].1;
+				self.word_timestamps[i].1 = prev.with_time(timestamp.into().time());
+			}
+			(Ok(i), None) => {
+				self.word_timestamps.remove(i);
+			}
+			(Err(i), Some(timestamp)) => self.word_timestamps.insert(i, (word, timestamp.into())),
+			(Err(_), None) => return,
+		}
+		self.source = None;
+	}
+
+	/// Whether `word_timestamps` are sorted by index and each lies on a char boundary of `text`
+	pub fn word_timestamps_fit(text: &str, word_timestamps: &[(usize, Timestamp)]) -> bool {
+		word_timestamps.is_sorted_by_key(|(idx, _)| *idx)
+			&& word_timestamps
+				.iter()
+				.all(|(idx, _)| text.is_char_boundary(*idx))
+	}
+
+	/// Whether the word timestamps of the line and of its secondary lines fit their text, which
+	/// is not a given for lines read back from the undo history
+	pub fn is_valid(&self) -> bool {
+		Self::word_timestamps_fit(&self.text, &self.word_timestamps)
+			&& self.secondary_lines.iter().all(|(_, line)| line.is_valid())
+	}
+
+	/// Replaces the text and its word timestamps
+	pub fn set_text(&mut self, text: String, word_timestamps: Vec<(usize, Timestamp)>) {
+		self.text = text;
+		self.word_timestamps = word_timestamps;
+		self.source = None;
+	}
+
+	/// Replaces the bytes in `range` of the text, word timestamps after it move along and those
+	/// inside it move to its start
+	pub fn replace_text(&mut self, range: Range<usize>, text: &str) {
+		self.text.replace_range(range.clone(), text);
+		for (idx, _) in &mut self.word_timestamps {
+			if *idx >= range.end && *idx > range.start {
+				*idx = *idx - range.len() + text.len();
+			} else if *idx > range.start {
+				*idx = range.start;
+			}
+		}
+		self.word_timestamps.dedup_by_key(|(idx, _)| *idx);
+		self.source = None;
+	}
+
+	/// Cuts the text off at byte `at` and returns the rest after the `skip` bytes following it, with
+	/// the word timestamps in it. A word timestamp at `at` stays if anything is skipped, as it then
+	/// marks the end of the text.
+	pub fn split_off(&mut self, at: usize, skip: usize) -> (String, Vec<(usize, Timestamp)>) {
+		let rest = self.text[at + skip..].to_owned();
+		self.text.truncate(at);
+		let split = self
+			.word_timestamps
+			.partition_point(|(idx, _)| *idx < at || (*idx == at && skip > 0));
+		let rest_word_timestamps = self
+			.word_timestamps
+			.drain(split..)
+			.filter(|(idx, _)| *idx >= at + skip)
+			.map(|(idx, timestamp)| (idx - at - skip, timestamp))
+			.collect();
+		self.source = None;
+		(rest, rest_word_timestamps)
+	}
+
+	/// Appends text with its word timestamps, after `separator`
+	pub fn append(&mut self, separator: &str, text: &str, word_timestamps: &[(usize, Timestamp)]) {
+		self.text.push_str(separator);
+		let start = self.text.len();
+		self.text.push_str(text);
+		for (idx, timestamp) in word_timestamps {
+			if self
+				.word_timestamps
+				.last()
+				.is_none_or(|(last, _)| *last < start + idx)
+			{
+				self.word_timestamps.push((start + idx, timestamp.clone()));
+			}
+		}
+		self.source = None;
+	}
+
+	/// Removes whitespace from the end of the text, word timestamps in it are moved to the end
+	pub fn trim_end(&mut self) {
+		let len = self.text.trim_end().len();
+		if len == self.text.len() {
+			return;
+		}
+		self.text.truncate(len);
+		for (idx, _) in &mut self.word_timestamps {
+			*idx = (*idx).min(len);
+		}
+		self.word_timestamps.dedup_by_key(|(idx, _)| *idx);
+		self.source = None;
+	}
+
+	/// Removes the line, repeat and word timestamps
+	pub fn clear_timestamps(&mut self) {
+		self.set_timestamp(None::<Timestamp>);
+		self.repeat_timestamps.clear();
+		self.word_timestamps.clear();
+	}
+
+	/// Replaces the line and word timestamps with the result of `f`
+	pub fn map_timestamps(&mut self, f: impl Fn(Duration) -> Duration) {
+		self.map_timestamps_dyn(&f);
+	}
+
+	fn map_timestamps_dyn(&mut self, f: &dyn Fn(Duration) -> Duration) {
+		if self.timestamp.is_none() && self.word_timestamps.is_empty() {
+			return;
+		}
+		if let Some(timestamp) = &mut self.timestamp {
+			*timestamp = timestamp.with_time(f(timestamp.time()));
+		}
+		for timestamp in &mut self.repeat_timestamps {
+			*timestamp = timestamp.with_time(f(timestamp.time()));
+		}
+		for (_, timestamp) in &mut self.word_timestamps {
+			*timestamp = timestamp.with_time(f(timestamp.time()));
+		}
+		self.source = None;
+
+		for (_, line) in &mut self.secondary_lines {
+			line.map_timestamps_dyn(f);
+		}
+	}
+
+	/// Text with the voice prefix and the word timestamps inserted as enhanced lrc inline tags
+	pub fn enhanced_text(&self, precision: TimestampPrecision) -> String {
+		let mut s = String::with_capacity(self.text.len() + self.word_timestamps.len() * 10);
+		if let Some(voice) = self.voice {
+			s.push_str(&format!("{voice}: "));
+		}
+		let mut prev = 0;
+		for (idx, timestamp) in &self.word_timestamps {
+			s.push_str(&self.text[prev..*idx]);
+			s.push('<');
+			s.push_str(&timestamp.format(precision));
+			s.push('>');
+			prev = *idx;
+		}
+		s.push_str(&self.text[prev..]);
+		s
+	}
+
+	/// Byte index of the character at display column `x`, or the text length if out of range
+	pub fn byte_index_at(&self, x: u16) -> usize {
+		self.text
+			.char_indices()
+			.scan(0, |pos, (idx, c)| {
+				*pos += c.width().unwrap_or_default() as u16;
+				Some((*pos, idx))
+			})
+			.find(|(end, _)| *end > x)
+			.map_or(self.text.len(), |(_, idx)| idx)
+	}
+
+	/// Display column at which the character at byte index `idx` starts
+	pub fn column_at(&self, idx: usize) -> u16 {
+		self.text[..idx.min(self.text.len())].width() as u16
+	}
+
+	/// Byte index of the start of the word at display column `x`, if there is a word there
+	pub fn word_at(&self, x: u16) -> Option<usize> {
+		let idx = self.byte_index_at(x);
+		if self.text[idx..]
+			.chars()
+			.next()
+			.is_none_or(char::is_whitespace)
+		{
+			return None;
+		}
+		Some(
+			self.text[..idx]
+				.char_indices()
+				.rev()
+				.find(|(_, c)| c.is_whitespace())
+				.map_or(0, |(i, c)| i + c.len_utf8()),
+		)
+	}
+
+	/// Byte index of the start of the first word after the one at byte index `idx`
+	pub fn next_word(&self, idx: usize) -> Option<usize> {
+		let after_word = self.text[idx..]
+			.find(char::is_whitespace)
+			.map(|i| idx + i)?;
+		self.text[after_word..]
+			.find(|c: char| !c.is_whitespace())
+			.map(|i| after_word + i)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_enhanced_word_timestamps() {
+		let line = LyricLine::parse_enhanced(None, "<00:01.00>one <00:01.50>two  <00:02.00> three");
+		assert_eq!(line.text(), "one two   three");
+		let words: Vec<_> = line
+			.word_timestamps()
+			.iter()
+			.map(|(idx, timestamp)| (*idx, timestamp.time()))
+			.collect();
+		assert_eq!(
+			words,
+			vec![
+				(0, Duration::from_millis(1000)),
+				(4, Duration::from_millis(1500)),
+				(9, Duration::from_millis(2000)),
+			]
+		);
+	}
+
+	#[test]
+	fn keeps_text_in_angle_brackets() {
+		let line = LyricLine::parse_enhanced(None, "a <b> c <00:01.00");
+		assert_eq!(line.text(), "a <b> c <00:01.00");
+		assert!(line.word_timestamps().is_empty());
+	}
 }
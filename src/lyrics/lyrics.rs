@@ -1,15 +1,24 @@
-use std::io::{BufRead, Read, Write};
+use std::{
+	borrow::Cow,
+	io::{BufRead, Write},
+	time::Duration,
+};
 
 use color_eyre::eyre;
 use unicode_width::UnicodeWidthStr;
 
-use super::{Timestamp, lyric_line::LyricLine, metadata::Metadata};
+use super::{
+	Diagnostic, Severity, Timestamp, TimestampPrecision, Voice,
+	lyric_line::LyricLine,
+	metadata::{Metadata, TagType},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Lyrics {
 	metadata: Vec<Metadata>,
 	lines: Vec<LyricLine>,
 	sync_percentage: u8,
+	final_newline: bool,
 }
 
 impl Default for Lyrics {
@@ -18,6 +27,7 @@ impl Default for Lyrics {
 			metadata: Default::default(),
 			lines: vec![LyricLine::default()],
 			sync_percentage: 0,
+			final_newline: true,
 		}
 	}
 }
@@ -39,41 +49,253 @@ impl Lyrics {
 		self.sync_percentage
 	}
 
-	pub fn read_overwrite(&mut self, reader: impl Read + BufRead) -> eyre::Result<()> {
+	pub fn from_lines(lines: Vec<LyricLine>) -> Self {
+		let mut lyrics = Self {
+			lines,
+			..Default::default()
+		};
+		if lyrics.lines.is_empty() {
+			lyrics.lines.push(Default::default());
+		}
+		lyrics.sync_percentage = lyrics.calc_sync_percentage();
+		lyrics
+	}
+
+	/// Replaces the lyrics with those read from `reader`, lines read from other files stay bound to
+	/// the line with the same timestamp or position. With `inline_translations`, see
+	/// [`Lyrics::bind_inline_translations`]. Returns the malformed parts of the input.
+	pub fn read_overwrite(
+		&mut self,
+		mut reader: impl BufRead,
+		inline_translations: bool,
+	) -> eyre::Result<Vec<Diagnostic>> {
+		let separate_lines: Vec<_> = self
+			.lines
+			.iter()
+			.enumerate()
+			.flat_map(|(index, line)| {
+				line.secondary_lines()
+					.iter()
+					.filter_map(move |(lang, secondary)| {
+						let in_sync = secondary.timestamp() == line.timestamp();
+						Some((index, lang.clone()?, secondary.clone(), in_sync))
+					})
+			})
+			.collect();
+
 		self.metadata.clear();
 		self.lines.clear();
-		for line in reader.lines() {
-			self.parse_append(&format!("{}\n", &line?));
-		}
+		let mut s = String::new();
+		reader.read_to_string(&mut s)?;
+		let diagnostics = self.parse_append(&s);
+		self.final_newline = s.is_empty() || s.ends_with('\n');
 		if self.lines.is_empty() {
 			self.lines.push(Default::default());
 		}
+		if inline_translations {
+			self.bind_inline_translations();
+		}
+		for (index, lang, line, in_sync) in separate_lines {
+			self.attach_secondary_line(index, &lang, line, in_sync);
+		}
 
 		self.sync_percentage = self.calc_sync_percentage();
 
-		Ok(())
+		Ok(diagnostics)
 	}
 
-	pub fn write_to(&self, writer: &mut impl Write) -> eyre::Result<()> {
-		for line in &self.lines {
-			if let Some(timestamp) = line.timestamp() {
-				writeln!(writer, "[{}] {}", timestamp.text(), line.text())?;
+	/// Turns every line directly following a line with the same timestamp, without tags between
+	/// them, into a translation or romanisation of that line
+	pub fn bind_inline_translations(&mut self) {
+		let mut index = 1;
+		while index < self.lines.len() {
+			let (prev, line) = (&self.lines[index - 1], &self.lines[index]);
+			if self.tags_before_line(index) == 0
+				&& prev.timestamp().is_some()
+				&& prev.timestamp() == line.timestamp()
+				&& prev.repeat_timestamps().is_empty()
+				&& line.repeat_timestamps().is_empty()
+				&& !prev.text().is_empty()
+				&& !line.text().is_empty()
+			{
+				let line = self.remove_line(index);
+				self.lines[index - 1].push_secondary_line(None, line);
 			} else {
-				writeln!(writer, "{}", line.text())?;
+				index += 1;
+			}
+		}
+	}
+
+	/// Binds the lines of a translation or romanisation read from a `song.<lang>.lrc` file to the
+	/// lines with the same timestamp, or else to the lines at the same position
+	pub fn attach_secondary_lyrics(&mut self, lang: &str, lyrics: Lyrics) {
+		for (index, line) in lyrics.lines.into_iter().enumerate() {
+			if !line.text().is_empty() {
+				self.attach_secondary_line(index, lang, line, false);
 			}
 		}
+	}
+
+	/// Binds a line read from another file, and moves it to the timestamp of the line it is bound to
+	/// if `follow` is set
+	fn attach_secondary_line(
+		&mut self,
+		index: usize,
+		lang: &str,
+		mut line: LyricLine,
+		follow: bool,
+	) {
+		let target = line
+			.timestamp()
+			.and_then(|timestamp| {
+				self.lines.iter().position(|main| {
+					main.timestamp() == Some(timestamp) && !main.has_secondary_line(Some(lang))
+				})
+			})
+			.unwrap_or(index.min(self.lines.len() - 1));
+		if follow {
+			line.set_timestamp(self.lines[target].timestamp().map(Timestamp::time));
+		}
+		self.lines[target].push_secondary_line(Some(lang.to_owned()), line);
+	}
+
+	/// Languages of the files secondary lines were read from
+	pub fn secondary_langs(&self) -> Vec<&str> {
+		let mut langs: Vec<_> = self
+			.lines
+			.iter()
+			.flat_map(|line| line.secondary_lines())
+			.filter_map(|(lang, _)| lang.as_deref())
+			.collect();
+		langs.sort_unstable();
+		langs.dedup();
+		langs
+	}
+
+	/// The lines read from the `song.<lang>.lrc` file, in the order of the lines they are bound to
+	pub fn secondary_lines(&self, lang: &str) -> Vec<LyricLine> {
+		self.lines
+			.iter()
+			.flat_map(|line| line.secondary_lines())
+			.filter(|(secondary_lang, _)| secondary_lang.as_deref() == Some(lang))
+			.map(|(_, line)| line.clone())
+			.collect()
+	}
+
+	/// Replaces the lines and keeps the metadata
+	pub fn set_lines(&mut self, lines: Vec<LyricLine>) {
+		self.lines = lines;
+		if self.lines.is_empty() {
+			self.lines.push(Default::default());
+		}
+		self.sync_percentage = self.calc_sync_percentage();
+	}
+
+	pub fn write_to(
+		&self,
+		writer: &mut impl Write,
+		precision: TimestampPrecision,
+	) -> eyre::Result<()> {
+		let mut metadata = self.metadata.iter().peekable();
+		let mut is_first_line = true;
+		let mut write_line = |writer: &mut dyn Write, text: &str| -> eyre::Result<()> {
+			if !is_first_line {
+				writeln!(writer)?;
+			}
+			is_first_line = false;
+			write!(writer, "{text}")?;
+			Ok(())
+		};
+
+		// a sole empty line is only a placeholder for the editor, not part of the file
+		let lines = if self.lines == [LyricLine::default()] {
+			&[]
+		} else {
+			self.lines.as_slice()
+		};
+
+		for (line_num, line) in lines.iter().enumerate() {
+			let mut inline_tag = None;
+			let mut split_off = false;
+			while let Some(meta) = metadata.next_if(|meta| meta.line_num() <= line_num) {
+				let is_last = metadata
+					.peek()
+					.is_none_or(|next| next.line_num() > line_num);
+				if meta.is_inline() && meta.line_num() == line_num && is_last {
+					inline_tag = Some(meta);
+				} else {
+					split_off |= meta.is_inline() && meta.line_num() == line_num;
+					write_line(writer, &meta.to_string())?;
+				}
+			}
+			let text = Self::format_line(line, precision);
+			match inline_tag {
+				// the source of the line keeps the whitespace between the tag and the text
+				Some(meta) if line.source_with_precision(precision).is_some() => {
+					write_line(writer, &format!("{meta}{text}"))?
+				}
+				Some(meta) => write_line(writer, &format!("{meta} {text}"))?,
+				// without the tag before it, the source still starts with that whitespace
+				None if split_off => write_line(writer, text.trim_start())?,
+				None => write_line(writer, &text)?,
+			}
+			for (_, secondary) in line
+				.secondary_lines()
+				.iter()
+				.filter(|(lang, _)| lang.is_none())
+			{
+				write_line(writer, &Self::format_line(secondary, precision))?;
+			}
+		}
+		for meta in metadata {
+			write_line(writer, &meta.to_string())?;
+		}
+		if self.final_newline && !is_first_line {
+			writeln!(writer)?;
+		}
+
 		writer.flush()?;
 		Ok(())
 	}
 
-	pub fn parse_append(&mut self, s: &str) {
-		let lines = s.lines();
-		for line in lines {
-			let mut it = line.trim_start().chars();
-			let first = it.next();
+	fn format_line(line: &LyricLine, precision: TimestampPrecision) -> Cow<'_, str> {
+		match (line.source_with_precision(precision), line.timestamp()) {
+			(Some(source), _) => Cow::Borrowed(source),
+			(None, Some(timestamp)) => {
+				let text = line.enhanced_text(precision);
+				Cow::Owned(format!(
+					"[{}]{}{}{text}",
+					timestamp.format(precision),
+					line.repeat_timestamps()
+						.iter()
+						.map(|timestamp| format!("[{}]", timestamp.format(precision)))
+						.collect::<String>(),
+					if text.is_empty() { "" } else { " " },
+				))
+			}
+			(None, None) => Cow::Owned(line.enhanced_text(precision)),
+		}
+	}
 
-			let (tag_delim, tag_end, _) = match first {
-				Some('[') => it.enumerate().fold(
+	/// Parses lrc text and appends its tags and lines, returns the parts which could not be read
+	/// the way they were most likely meant
+	pub fn parse_append(&mut self, s: &str) -> Vec<Diagnostic> {
+		let mut diagnostics = Vec::new();
+		for (line_idx, line) in s.lines().enumerate() {
+			let trimmed = line.trim_start();
+			let indent = line.len() - trimmed.len();
+			let mut diagnose = |idx: usize, severity: Severity, message: String| {
+				diagnostics.push(Diagnostic::new(line_idx, line, idx, severity, message));
+			};
+
+			if let Some(comment) = trimmed.strip_prefix('#') {
+				self.metadata
+					.push(Metadata::comment(comment, self.lines.len()).with_source(line));
+				continue;
+			}
+
+			let (tag_delim, tag_end, _) = match trimmed.chars().next() {
+				Some('[') => trimmed[1..].char_indices().fold(
 					(None, None, false),
 					|(colon_i, close_i, escape), (i, c)| {
 						if close_i.is_some() || escape {
@@ -97,41 +319,148 @@ impl Lyrics {
 			};
 
 			if let (Some(tag_delim), Some(tag_end)) = (tag_delim, tag_end) {
-				let line = line.trim_start();
 				self.parse_append_line_with_tag(
-					&line[1..tag_end + 1],
+					&trimmed[1..tag_end + 1],
 					tag_delim,
-					&line[tag_end + 2..],
+					&trimmed[tag_end + 2..],
+					line,
+					&mut diagnose,
 				);
 			} else {
-				self.parse_append_line(line);
+				// section markers like `[Chorus]` are common in plain lyrics, so only
+				// brackets that look like a mistyped timestamp are reported
+				if let Some(rest) = trimmed.strip_prefix('[') {
+					let message = if !rest.contains(']') {
+						Some("Unclosed tag bracket, the line is read as lyrics")
+					} else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+						Some("Tag without a colon, the line is read as lyrics")
+					} else {
+						None
+					};
+					if let Some(message) = message {
+						diagnose(indent, Severity::Warning, message.to_owned());
+					}
+				}
+				Self::diagnose_word_timestamps(line, line, &mut diagnose);
+				self.parse_append_line(line, line);
 			}
 		}
 
 		self.sync_percentage = self.calc_sync_percentage();
+		diagnostics
+	}
+
+	/// Reports inline tags which look like word timestamps but are kept as text
+	fn diagnose_word_timestamps(
+		text: &str,
+		source: &str,
+		diagnose: &mut impl FnMut(usize, Severity, String),
+	) {
+		// byte index of `text` in `source`, which it is a slice of
+		let start = text.as_ptr() as usize - source.as_ptr() as usize;
+		let mut rest = text;
+		while let Some(open) = rest.find('<') {
+			let Some(close) = rest[open..].find('>').map(|i| open + i) else {
+				break;
+			};
+			let tag = &rest[open + 1..close];
+			if Self::looks_like_timestamp(tag)
+				&& let Err(e) = tag.parse::<Timestamp>()
+			{
+				let idx = start + (text.len() - rest.len()) + open;
+				diagnose(
+					idx,
+					Severity::Error,
+					format!("{e} in <{tag}>, it is read as lyrics"),
+				);
+			}
+			rest = &rest[close + 1..];
+		}
+	}
+
+	fn looks_like_timestamp(tag: &str) -> bool {
+		tag.trim_start().starts_with(|c: char| c.is_ascii_digit()) && tag.contains(':')
 	}
 
-	fn parse_append_line_with_tag(&mut self, tag: &str, tag_delim: usize, mut text: &str) {
+	fn parse_append_line_with_tag(
+		&mut self,
+		tag: &str,
+		tag_delim: usize,
+		mut text: &str,
+		source: &str,
+		diagnose: &mut impl FnMut(usize, Severity, String),
+	) {
+		let tag_idx = source.len() - text.len() - tag.len() - 2;
 		if let Ok(timestamp) = tag.parse() {
+			let mut repeat_timestamps = Vec::new();
+			while let Some((repeat_tag, rest)) =
+				text.strip_prefix('[').and_then(|s| s.split_once(']'))
+			{
+				match repeat_tag.parse() {
+					Ok(repeat_timestamp) => repeat_timestamps.push(repeat_timestamp),
+					Err(e) => {
+						if Self::looks_like_timestamp(repeat_tag) {
+							diagnose(
+								source.len() - text.len(),
+								Severity::Error,
+								format!("{e} in [{repeat_tag}], it is read as lyrics"),
+							);
+						}
+						break;
+					}
+				}
+				text = rest;
+			}
 			if text.starts_with(' ') {
 				text = &text[1..];
 			}
-			self.lines
-				.push(LyricLine::new(Some(timestamp), text.to_owned()));
+			Self::diagnose_word_timestamps(text, source, diagnose);
+			let line = LyricLine::parse_enhanced(Some(timestamp), text)
+				.with_repeat_timestamps(repeat_timestamps)
+				.with_source(source);
+
+			self.lines.push(line);
 		} else {
-			self.metadata.push(Metadata::parse_separate(
+			if Self::looks_like_timestamp(tag)
+				&& let Err(e) = tag.parse::<Timestamp>()
+			{
+				diagnose(
+					tag_idx,
+					Severity::Error,
+					format!("{e} in [{tag}], it is read as a tag"),
+				);
+			}
+			let metadata = Metadata::parse_separate(
 				&tag[..tag_delim],
 				&tag[tag_delim + 1..],
-			));
-			text = text.trim();
-			if !text.is_empty() {
-				self.parse_append_line(text);
+				self.lines.len(),
+			);
+			if *metadata.tag_type() == TagType::Offset && metadata.value().parse::<i64>().is_err() {
+				diagnose(
+					tag_idx,
+					Severity::Warning,
+					"Offset is not a whole number of milliseconds, it is ignored".to_owned(),
+				);
+			}
+			if text.trim().is_empty() {
+				self.metadata.push(metadata.with_source(source));
+			} else {
+				let tag_source = &source[..source.len() - text.len()];
+				self.metadata
+					.push(metadata.with_source(tag_source).inline());
+				Self::diagnose_word_timestamps(text, source, diagnose);
+				self.parse_append_line(text.trim(), text);
 			}
 		}
 	}
 
-	fn parse_append_line(&mut self, line: &str) {
-		self.lines.push(LyricLine::new(None, line.to_owned()));
+	fn parse_append_line(&mut self, line: &str, source: &str) {
+		self.lines
+			.push(LyricLine::parse_enhanced(None, line).with_source(source));
+	}
+
+	pub fn metadata(&self) -> &[Metadata] {
+		self.metadata.as_slice()
 	}
 
 	pub fn lines(&self) -> &[LyricLine] {
@@ -146,17 +475,439 @@ impl Lyrics {
 		self.lines.iter().map(|x| x.text().width() as u16)
 	}
 
+	/// Display width of the widest line timestamp, at least that of `mm:ss.xx`
+	pub fn timestamp_width(&self) -> u16 {
+		self.lines
+			.iter()
+			.map(|line| line.timestamp_text().len() as u16)
+			.max()
+			.unwrap_or_default()
+			.max(8)
+	}
+
 	pub fn time_at_line(&self, y: u16) -> Option<&Timestamp> {
 		self.lines.get(y as usize).and_then(|line| line.timestamp())
 	}
 
-	pub fn time_at_cursor(&self, _x: u16, y: u16) -> Option<&Timestamp> {
-		// TODO: karaoke: use x position to get word if possible, fallback to line
-		self.lines.get(y as usize).and_then(|line| line.timestamp())
+	pub fn time_at_cursor(&self, x: u16, y: u16) -> Option<&Timestamp> {
+		let line = self.lines.get(y as usize)?;
+		line.word_at(x)
+			.and_then(|word| line.word_timestamp(word))
+			.or(line.timestamp())
+	}
+
+	pub fn word_timestamp_at_cursor(&self, x: u16, y: u16) -> Option<(usize, &Timestamp)> {
+		let line = self.lines.get(y as usize)?;
+		line.word_at(x)
+			.and_then(|word| line.word_timestamp(word).map(|timestamp| (word, timestamp)))
 	}
 
 	pub fn set_timestamp_at_line(&mut self, index: usize, timestamp: Option<impl Into<Timestamp>>) {
 		self.lines[index].set_timestamp(timestamp);
 		self.sync_percentage = self.calc_sync_percentage();
 	}
+
+	pub fn set_word_timestamp_at_line(
+		&mut self,
+		index: usize,
+		word: usize,
+		timestamp: Option<impl Into<Timestamp>>,
+	) {
+		self.lines[index].set_word_timestamp(word, timestamp);
+	}
+
+	pub fn set_text_at_line(
+		&mut self,
+		index: usize,
+		text: String,
+		word_timestamps: Vec<(usize, Timestamp)>,
+	) {
+		self.lines[index].set_text(text, word_timestamps);
+		self.sync_percentage = self.calc_sync_percentage();
+	}
+
+	pub fn trim_line_end(&mut self, index: usize) {
+		self.lines[index].trim_end();
+	}
+
+	pub fn set_voice_at_line(&mut self, index: usize, voice: Option<Voice>) {
+		self.lines[index].set_voice(voice);
+	}
+
+	/// Whether any line uses the F/M/D voices rather than numbered ones
+	pub fn has_gendered_voices(&self) -> bool {
+		self.lines
+			.iter()
+			.any(|line| line.voice().is_some_and(Voice::is_gendered))
+	}
+
+	/// The `[offset:]` tag value in milliseconds, which is added to every timestamp during playback
+	pub fn offset(&self) -> i64 {
+		self.tag(&TagType::Offset)
+			.and_then(|value| value.parse().ok())
+			.unwrap_or_default()
+	}
+
+	pub fn set_offset(&mut self, offset: Option<i64>) {
+		self.set_tag(TagType::Offset, offset.map(|offset| format!("{offset:+}")));
+	}
+
+	/// The value of the last tag of the given type
+	pub fn tag(&self, tag_type: &TagType) -> Option<&str> {
+		self.metadata
+			.iter()
+			.rev()
+			.find(|meta| meta.tag_type() == tag_type)
+			.map(|meta| meta.value())
+	}
+
+	/// Changes the value of the last tag of the given type, adds it to the end of the header if
+	/// there is none yet, before a tag at the start of the first line, or removes it if `value` is
+	/// `None`
+	pub fn set_tag(&mut self, tag_type: TagType, value: Option<String>) {
+		let existing = self
+			.metadata
+			.iter()
+			.rposition(|meta| *meta.tag_type() == tag_type);
+		match (existing, value) {
+			(Some(i), Some(value)) => self.metadata[i].set_value(value),
+			(Some(i), None) => {
+				self.metadata.remove(i);
+			}
+			(None, Some(value)) => {
+				let header_end = self
+					.metadata
+					.iter()
+					.position(|meta| meta.line_num() > 0 || meta.is_inline())
+					.unwrap_or(self.metadata.len());
+				self.metadata
+					.insert(header_end, Metadata::new(tag_type, value, 0));
+			}
+			(None, None) => (),
+		}
+	}
+
+	pub fn apply_offset(&self, time: Duration) -> Duration {
+		Self::offset_time(time, self.offset())
+	}
+
+	pub fn remove_offset(&self, time: Duration) -> Duration {
+		Self::offset_time(time, -self.offset())
+	}
+
+	/// Moves a time by `offset` milliseconds, stopping at zero
+	pub fn offset_time(time: Duration, offset: i64) -> Duration {
+		Duration::from_millis((time.as_millis() as i64).saturating_add(offset).max(0) as u64)
+	}
+
+	/// Adds the offset to every timestamp and removes the `[offset:]` tag
+	pub fn bake_offset(&mut self) {
+		let offset = self.offset();
+		for line in &mut self.lines {
+			line.map_timestamps(|time| Self::offset_time(time, offset));
+		}
+		self.metadata
+			.retain(|meta| *meta.tag_type() != TagType::Offset);
+	}
+
+	/// Number of tags written between the line before `index` and the line at `index`
+	pub fn tags_before_line(&self, index: usize) -> usize {
+		self.metadata
+			.iter()
+			.filter(|meta| meta.line_num() == index)
+			.count()
+	}
+
+	/// Inserts a line at `index`, after the first `tags_before` of the tags which were before the
+	/// line at `index` and before the rest of them
+	pub fn insert_line(&mut self, index: usize, line: LyricLine, tags_before: usize) {
+		self.lines.insert(index, line);
+		let mut before = 0;
+		for meta in &mut self.metadata {
+			if meta.line_num() == index && before < tags_before {
+				before += 1;
+			} else if meta.line_num() >= index {
+				meta.set_line_num(meta.line_num() + 1);
+			}
+		}
+		self.sync_percentage = self.calc_sync_percentage();
+	}
+
+	/// Removes a line, the tags before and after it end up before the line after it. The last
+	/// remaining line is replaced with an empty line instead.
+	pub fn remove_line(&mut self, index: usize) -> LyricLine {
+		for meta in &mut self.metadata {
+			if meta.line_num() > index {
+				meta.set_line_num(meta.line_num() - 1);
+			}
+		}
+		let line = self.lines.remove(index);
+		if self.lines.is_empty() {
+			self.lines.push(Default::default());
+		}
+		self.sync_percentage = self.calc_sync_percentage();
+		line
+	}
+
+	/// Cuts line `index` at byte `at`, drops the `skip` bytes after it and inserts the rest as the
+	/// text of `next` after it, see [`Lyrics::insert_line`] for `tags_before`
+	pub fn split_line(
+		&mut self,
+		index: usize,
+		at: usize,
+		skip: usize,
+		mut next: LyricLine,
+		tags_before: usize,
+	) {
+		let (text, word_timestamps) = self.lines[index].split_off(at, skip);
+		next.set_text(text, word_timestamps);
+		self.insert_line(index + 1, next, tags_before);
+	}
+
+	/// Appends the text and word timestamps of the line after `index` to it, after `separator`,
+	/// and removes that line, which should have the same voice and no translations as those are
+	/// dropped
+	pub fn join_lines(&mut self, index: usize, separator: &str) {
+		let next = self.remove_line(index + 1);
+		self.lines[index].append(separator, next.text(), next.word_timestamps());
+		self.sync_percentage = self.calc_sync_percentage();
+	}
+
+	/// Replaces as many lines as given, starting at `index`
+	pub fn replace_lines(&mut self, index: usize, lines: Vec<LyricLine>) {
+		self.lines.splice(index..index + lines.len(), lines);
+		self.sync_percentage = self.calc_sync_percentage();
+	}
+
+	/// Replaces the `remove` lines starting at `index` with `lines`, the tags stay where they are
+	pub fn splice_lines(&mut self, index: usize, remove: usize, lines: Vec<LyricLine>) {
+		self.lines.splice(index..index + remove, lines);
+		if self.lines.is_empty() {
+			self.lines.push(Default::default());
+		}
+		self.sync_percentage = self.calc_sync_percentage();
+	}
+
+	/// Replaces all tags, and whether the file ends with a newline
+	pub fn set_metadata(&mut self, metadata: Vec<Metadata>, final_newline: bool) {
+		self.metadata = metadata;
+		self.final_newline = final_newline;
+	}
+
+	pub fn final_newline(&self) -> bool {
+		self.final_newline
+	}
+
+	/// Moves a line to another position, the tags stay where they are
+	pub fn move_line(&mut self, from: usize, to: usize) {
+		let line = self.lines.remove(from);
+		self.lines.insert(to, line);
+	}
+
+	/// Turns every additional timestamp of a line into a separate copy of that line,
+	/// placed after the last line with an earlier timestamp
+	pub fn expand_repeats(&mut self) {
+		let mut copies = Vec::new();
+		for line in &mut self.lines {
+			let Some(primary) = line.timestamp().map(|timestamp| timestamp.time()) else {
+				continue;
+			};
+			for repeat_timestamp in line.take_repeat_timestamps() {
+				let shift = repeat_timestamp.time().as_millis() as i64 - primary.as_millis() as i64;
+				let mut copy = line.clone();
+				copy.map_timestamps(|time| Self::offset_time(time, shift));
+				copies.push(copy);
+			}
+		}
+		copies.sort_by_key(|copy| copy.timestamp().cloned());
+
+		for copy in copies {
+			let index = self
+				.lines
+				.iter()
+				.rposition(|line| {
+					line.timestamp()
+						.is_some_and(|t| Some(t) <= copy.timestamp())
+				})
+				.map_or(0, |i| i + 1);
+			let tags_before = self.tags_before_line(index);
+			self.insert_line(index, copy, tags_before);
+		}
+
+		self.sync_percentage = self.calc_sync_percentage();
+	}
+
+	/// Merges synced lines with identical text, voice and translations into the first of them,
+	/// keeping the timestamps of the others as additional timestamps
+	pub fn compress_repeats(&mut self) {
+		let mut i = 0;
+		while i < self.lines.len() {
+			if self.lines[i].timestamp().is_none()
+				|| self.lines[i].text().trim().is_empty()
+				|| !self.lines[i].word_timestamps().is_empty()
+			{
+				i += 1;
+				continue;
+			}
+
+			let mut j = i + 1;
+			while j < self.lines.len() {
+				let is_repeat = self.lines[j].text() == self.lines[i].text()
+					&& self.lines[j].word_timestamps().is_empty()
+					&& self.lines[j].voice() == self.lines[i].voice()
+					&& Self::same_secondary_texts(&self.lines[i], &self.lines[j]);
+				match self.lines[j].timestamp().cloned() {
+					Some(timestamp) if is_repeat => {
+						let mut repeat_timestamps = self.lines[i].take_repeat_timestamps();
+						repeat_timestamps.push(timestamp);
+						repeat_timestamps.extend(self.lines[j].take_repeat_timestamps());
+						self.lines[i].set_repeat_timestamps(repeat_timestamps);
+						self.remove_line(j);
+					}
+					_ => j += 1,
+				}
+			}
+			i += 1;
+		}
+
+		self.sync_percentage = self.calc_sync_percentage();
+	}
+
+	/// Whether both lines have translations with the same languages and texts, whose timestamps
+	/// follow the lines anyway
+	fn same_secondary_texts(a: &LyricLine, b: &LyricLine) -> bool {
+		a.secondary_lines().len() == b.secondary_lines().len()
+			&& a.secondary_lines().iter().zip(b.secondary_lines()).all(
+				|((a_lang, a_line), (b_lang, b_line))| {
+					a_lang == b_lang && a_line.text() == b_line.text()
+				},
+			)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	fn write(lyrics: &Lyrics, precision: TimestampPrecision) -> String {
+		let mut out = Vec::new();
+		lyrics.write_to(&mut out, precision).unwrap();
+		String::from_utf8(out).unwrap()
+	}
+
+	/// Drops the sources the lines were read from, so they are written from their parts
+	fn rewritten(lyrics: &Lyrics) -> Lyrics {
+		let mut lyrics = lyrics.clone();
+		for line in &mut lyrics.lines {
+			line.set_text(line.text().to_owned(), line.word_timestamps().to_vec());
+		}
+		lyrics
+	}
+
+	#[test]
+	fn enhanced_lines_round_trip() {
+		let s = "[ar:someone]\n\
+			[00:01.00]<00:01.00>one <00:01.50>two  <00:02.00>three\n\
+			[00:03.00]v1: <00:03.25>voiced <00:03.75>words\n\
+			[00:04.00]a <b> c\n";
+		let lyrics = parse(s);
+		assert_eq!(lyrics.lines()[0].text(), "one two  three");
+		assert_eq!(lyrics.lines()[0].word_timestamps().len(), 3);
+		assert_eq!(lyrics.lines()[1].voice(), Some(Voice::Numbered(1)));
+		assert_eq!(write(&lyrics, TimestampPrecision::Keep), s);
+		assert_eq!(
+			write(&rewritten(&lyrics), TimestampPrecision::Keep),
+			"[ar:someone]\n\
+			[00:01.00] <00:01.00>one <00:01.50>two  <00:02.00>three\n\
+			[00:03.00] v1: <00:03.25>voiced <00:03.75>words\n\
+			[00:04.00] a <b> c\n"
+		);
+	}
+
+	#[test]
+	fn inline_tag_round_trips() {
+		let s = "[ti:title]  first\n[ar:x]\n[00:02.00]second\n";
+		let lyrics = parse(s);
+		assert_eq!(lyrics.line_count(), 2);
+		assert_eq!(lyrics.tag(&TagType::Title), Some("title"));
+		assert_eq!(lyrics.lines()[0].text(), "first");
+		assert_eq!(write(&lyrics, TimestampPrecision::Keep), s);
+
+		let mut lyrics = lyrics;
+		lyrics.set_text_at_line(0, "changed".to_owned(), Vec::new());
+		assert_eq!(
+			write(&lyrics, TimestampPrecision::Keep),
+			"[ti:title] changed\n[ar:x]\n[00:02.00]second\n"
+		);
+	}
+
+	#[test]
+	fn new_tag_goes_before_an_inline_tag() {
+		let mut lyrics = parse("[ti:title]  first\n[00:02.00]second\n");
+		lyrics.set_tag(TagType::Artist, Some("someone".to_owned()));
+		assert_eq!(
+			write(&lyrics, TimestampPrecision::Keep),
+			"[ar:someone]\n[ti:title]  first\n[00:02.00]second\n"
+		);
+
+		let mut lyrics = parse("[ar:x]\n[ti:title]  first\n");
+		lyrics.set_tag(TagType::Album, Some("album".to_owned()));
+		assert_eq!(
+			write(&lyrics, TimestampPrecision::Keep),
+			"[ar:x]\n[al:album]\n[ti:title]  first\n"
+		);
+	}
+
+	#[test]
+	fn inline_tag_split_off_drops_the_whitespace_after_it() {
+		let mut lyrics = parse("[ti:title]  first\n");
+		let mut metadata = lyrics.metadata().to_vec();
+		metadata.push(Metadata::new(TagType::Artist, "someone".to_owned(), 0));
+		lyrics.set_metadata(metadata, true);
+		assert_eq!(
+			write(&lyrics, TimestampPrecision::Keep),
+			"[ti:title]\n[ar:someone]\nfirst\n"
+		);
+	}
+
+	#[test]
+	fn millisecond_and_hour_timestamps_round_trip() {
+		let s = "[00:01.234]millis\n[1:02:03.45]hours\n[00:05.6]tenths\n";
+		let lyrics = parse(s);
+		let times: Vec<_> = lyrics
+			.lines()
+			.iter()
+			.map(|line| line.timestamp().unwrap().time())
+			.collect();
+		assert_eq!(
+			times,
+			[
+				Duration::from_millis(1_234),
+				Duration::from_millis(3_723_450),
+				Duration::from_millis(5_600),
+			]
+		);
+		assert_eq!(write(&lyrics, TimestampPrecision::Keep), s);
+		assert_eq!(
+			write(&rewritten(&lyrics), TimestampPrecision::Keep),
+			"[00:01.234] millis\n[1:02:03.45] hours\n[00:05.6] tenths\n"
+		);
+		assert_eq!(
+			write(&rewritten(&lyrics), TimestampPrecision::Centis),
+			"[00:01.23] millis\n[1:02:03.45] hours\n[00:05.60] tenths\n"
+		);
+	}
+
+	#[test]
+	fn huge_timestamp_is_diagnosed() {
+		let mut lyrics = Lyrics::default();
+		let diagnostics = lyrics.parse_append("[18446744073709551615:00.00]too late\n");
+		assert!(!diagnostics.is_empty());
+	}
 }
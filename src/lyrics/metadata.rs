@@ -1,4 +1,8 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TagType {
 	Title,
 	Artist,
@@ -11,22 +15,80 @@ pub enum TagType {
 	Tool,
 	Version,
 	Misc(String),
+	/// not an actual tag, but a line starting with `#`
+	Comment,
+}
+
+impl TagType {
+	pub fn key(&self) -> &str {
+		match self {
+			TagType::Title => "ti",
+			TagType::Artist => "ar",
+			TagType::Album => "al",
+			TagType::Author => "au",
+			TagType::Lyricist => "lr",
+			TagType::Length => "length",
+			TagType::By => "by",
+			TagType::Offset => "offset",
+			TagType::Tool => "re",
+			TagType::Version => "ve",
+			TagType::Misc(s) => s,
+			TagType::Comment => "#",
+		}
+	}
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Metadata {
 	tag_type: TagType,
 	value: String,
+	/// index of the lyric line this tag is written before
+	line_num: usize,
+	/// the line as it was read, written back as long as the tag is unchanged
+	source: Option<String>,
+	/// whether the tag was read at the start of the lyric line it is written before
+	#[serde(default)]
+	inline: bool,
 }
 
 impl Metadata {
-	pub fn parse_separate(tag_type: &str, value: &str) -> Self {
+	pub fn new(tag_type: TagType, value: String, line_num: usize) -> Self {
 		Self {
-			tag_type: Self::parse_tag_type(tag_type),
-			value: value.trim().to_owned(),
+			tag_type,
+			value,
+			line_num,
+			source: None,
+			inline: false,
 		}
 	}
 
+	pub fn parse_separate(tag_type: &str, value: &str, line_num: usize) -> Self {
+		Self::new(
+			Self::parse_tag_type(tag_type),
+			value.trim().to_owned(),
+			line_num,
+		)
+	}
+
+	pub fn comment(text: &str, line_num: usize) -> Self {
+		Self::new(TagType::Comment, text.to_owned(), line_num)
+	}
+
+	pub fn with_source(mut self, source: &str) -> Self {
+		self.source = Some(source.to_owned());
+		self
+	}
+
+	/// Marks the tag as read at the start of the lyric line it is written before
+	pub fn inline(mut self) -> Self {
+		self.inline = true;
+		self
+	}
+
+	pub fn is_inline(&self) -> bool {
+		self.inline
+	}
+
 	fn parse_tag_type(s: &str) -> TagType {
 		match s.trim() {
 			"ti" => TagType::Title,
@@ -42,4 +104,35 @@ impl Metadata {
 			s => TagType::Misc(s.to_owned()),
 		}
 	}
+
+	pub fn tag_type(&self) -> &TagType {
+		&self.tag_type
+	}
+
+	pub fn value(&self) -> &str {
+		&self.value
+	}
+
+	pub fn set_value(&mut self, value: String) {
+		self.value = value;
+		self.source = None;
+	}
+
+	pub fn line_num(&self) -> usize {
+		self.line_num
+	}
+
+	pub fn set_line_num(&mut self, line_num: usize) {
+		self.line_num = line_num;
+	}
+}
+
+impl fmt::Display for Metadata {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match (&self.source, &self.tag_type) {
+			(Some(source), _) => f.write_str(source),
+			(None, TagType::Comment) => write!(f, "#{}", self.value),
+			(None, tag_type) => write!(f, "[{}:{}]", tag_type.key(), self.value),
+		}
+	}
 }
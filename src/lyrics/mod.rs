@@ -1,11 +1,22 @@
+mod diagnostic;
 pub mod editing;
+mod encoding;
+mod formats;
+pub mod lint;
 mod lyric_line;
+#[allow(clippy::module_inception)]
 mod lyrics;
 mod metadata;
 mod time_index;
 mod timestamp;
+mod voice;
 
+pub use diagnostic::{Diagnostic, Severity};
+pub use encoding::FileEncoding;
+pub use formats::{DEFAULT_ASS_STYLES, LyricsFormat, WriteOptions, embedded};
 pub use lyric_line::LyricLine;
 pub use lyrics::Lyrics;
+pub use metadata::{Metadata, TagType};
 pub use time_index::{TimeIndex, TimeIndexEntry, TimeIndexHint};
-pub use timestamp::Timestamp;
+pub use timestamp::{Timestamp, TimestampPrecision};
+pub use voice::Voice;
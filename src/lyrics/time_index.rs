@@ -1,11 +1,12 @@
 use std::{cmp, time::Duration};
 
-use super::lyric_line::LyricLine;
+use super::Lyrics;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct TimeIndexEntry {
 	pub time: Duration,
 	pub line_num: Option<u16>,
+	pub x: u16,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -24,13 +25,14 @@ impl Default for TimeIndex {
 			entries: vec![TimeIndexEntry {
 				time: Duration::ZERO,
 				line_num: None,
+				x: 0,
 			}],
 		}
 	}
 }
 
 impl TimeIndex {
-	pub fn new<'a>(lyrics: impl Iterator<Item = &'a LyricLine>) -> Self {
+	pub fn new(lyrics: &Lyrics) -> Self {
 		let mut x = Self {
 			entries: Vec::default(),
 		};
@@ -38,27 +40,78 @@ impl TimeIndex {
 		x
 	}
 
-	pub fn rebuild<'a>(&mut self, lyrics: impl Iterator<Item = &'a LyricLine>) {
+	pub fn rebuild(&mut self, lyrics: &Lyrics) {
 		self.entries.clear();
-		self.entries.extend(
-			std::iter::once(TimeIndexEntry {
-				time: Duration::ZERO,
-				line_num: None,
-			})
-			.chain(
-				lyrics
-					.enumerate()
-					.filter_map(|(i, line)| match line.timestamp() {
-						Some(timestamp) => Some(TimeIndexEntry {
-							time: timestamp.time(),
-							line_num: Some(i as u16),
-						}),
-						None => None,
-					}),
-			),
-		);
-		self.entries
-			.sort_unstable_by(|a, b| a.time.cmp(&b.time).then(a.line_num.cmp(&b.line_num)));
+		self.entries.push(TimeIndexEntry {
+			time: Duration::ZERO,
+			line_num: None,
+			x: 0,
+		});
+		for i in 0..lyrics.lines().len() {
+			self.entries.extend(Self::line_entries(lyrics, i));
+		}
+		self.entries.sort_unstable_by(Self::compare);
+	}
+
+	/// Updates the entries after the `removed` lines starting at `idx` were replaced by `inserted`
+	/// lines, without touching the entries of other lines besides shifting their line numbers
+	pub fn update_lines(&mut self, lyrics: &Lyrics, idx: usize, removed: usize, inserted: usize) {
+		let end = idx + removed;
+		self.entries.retain(|entry| {
+			entry
+				.line_num
+				.is_none_or(|line_num| !(idx..end).contains(&(line_num as usize)))
+		});
+		if removed != inserted {
+			for entry in &mut self.entries {
+				if let Some(line_num) = &mut entry.line_num
+					&& *line_num as usize >= end
+				{
+					*line_num = (*line_num as usize + inserted - removed) as u16;
+				}
+			}
+		}
+
+		let mut new_entries: Vec<_> = (idx..idx + inserted)
+			.flat_map(|i| Self::line_entries(lyrics, i))
+			.collect();
+		new_entries.sort_unstable_by(Self::compare);
+		let mut from = 0;
+		for entry in new_entries {
+			from += self.entries[from..].partition_point(|e| Self::compare(e, &entry).is_lt());
+			self.entries.insert(from, entry);
+			from += 1;
+		}
+	}
+
+	fn line_entries(lyrics: &Lyrics, i: usize) -> impl Iterator<Item = TimeIndexEntry> + '_ {
+		let line = &lyrics.lines()[i];
+		let line_entries = line
+			.timestamp()
+			.into_iter()
+			.chain(line.repeat_timestamps())
+			.map(move |timestamp| TimeIndexEntry {
+				time: lyrics.apply_offset(timestamp.time()),
+				line_num: Some(i as u16),
+				x: 0,
+			});
+		let word_entries = line
+			.word_timestamps()
+			.iter()
+			.filter(|(idx, _)| *idx < line.text().len())
+			.map(move |(idx, timestamp)| TimeIndexEntry {
+				time: lyrics.apply_offset(timestamp.time()),
+				line_num: Some(i as u16),
+				x: line.column_at(*idx),
+			});
+		line_entries.chain(word_entries)
+	}
+
+	fn compare(a: &TimeIndexEntry, b: &TimeIndexEntry) -> cmp::Ordering {
+		a.time
+			.cmp(&b.time)
+			.then(a.line_num.cmp(&b.line_num))
+			.then(a.x.cmp(&b.x))
 	}
 
 	pub fn find_random(&self, time: Duration) -> (TimeIndexEntry, TimeIndexHint) {
@@ -78,6 +131,7 @@ impl TimeIndex {
 			TimeIndexEntry {
 				time: Duration::ZERO,
 				line_num: None,
+				x: 0,
 			},
 			TimeIndexHint { idx: 0 },
 		)
@@ -91,6 +145,7 @@ impl TimeIndex {
 					TimeIndexEntry {
 						time: Duration::ZERO,
 						line_num: None,
+						x: 0,
 					},
 					TimeIndexHint { idx: 0 },
 				);
@@ -103,3 +158,100 @@ impl TimeIndex {
 		(self.entries[idx], TimeIndexHint { idx })
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::lyrics::{LyricLine, editing::EditAction};
+
+	fn parse(s: &str) -> Lyrics {
+		let mut lyrics = Lyrics::default();
+		lyrics.read_overwrite(s.as_bytes(), false).unwrap();
+		lyrics
+	}
+
+	/// Executes `action`, which updates the index line by line, and checks that the result is the
+	/// same as indexing the lyrics from scratch
+	fn assert_matches_rebuild(s: &str, action: EditAction) {
+		let mut lyrics = parse(s);
+		let mut time_index = TimeIndex::new(&lyrics);
+		action.execute(&mut lyrics, &mut time_index).unwrap();
+		assert_eq!(time_index, TimeIndex::new(&lyrics), "after {action:?}");
+	}
+
+	const LYRICS: &str = "[offset:+100]\n\
+		[00:01.00]one <00:01.50>two\n\
+		[00:04.00]three\n\
+		[00:02.00][00:06.00]four\n\
+		[00:03.00]five <00:03.20>six <00:03.40>seven\n";
+
+	#[test]
+	fn split_matches_rebuild() {
+		assert_matches_rebuild(
+			LYRICS,
+			EditAction::SplitLine {
+				idx: 3,
+				at: 4,
+				skip: 1,
+				next: Box::new(LyricLine::new(None, String::new())),
+				tags_before: 0,
+			},
+		);
+	}
+
+	#[test]
+	fn join_matches_rebuild() {
+		for idx in 0..3 {
+			assert_matches_rebuild(
+				LYRICS,
+				EditAction::JoinLines {
+					idx,
+					separator: " ".to_owned(),
+				},
+			);
+		}
+	}
+
+	#[test]
+	fn move_matches_rebuild() {
+		for (from, to) in [(0, 3), (3, 0), (1, 2), (2, 2)] {
+			assert_matches_rebuild(LYRICS, EditAction::MoveLine { from, to });
+		}
+	}
+
+	#[test]
+	fn remove_matches_rebuild() {
+		assert_matches_rebuild(LYRICS, EditAction::RemoveLine { idx: 1 });
+		assert_matches_rebuild(LYRICS, EditAction::RemoveLine { idx: 3 });
+		// the last line is replaced with an empty one
+		assert_matches_rebuild(
+			"[00:01.00]one <00:01.50>two\n",
+			EditAction::RemoveLine { idx: 0 },
+		);
+	}
+
+	#[test]
+	fn splice_matches_rebuild() {
+		let lines = parse("[00:05.00]new <00:05.50>words\n[00:00.50]early\n")
+			.lines()
+			.to_vec();
+		for (idx, remove) in [(0, 0), (1, 2), (2, 2), (4, 0), (0, 4)] {
+			assert_matches_rebuild(
+				LYRICS,
+				EditAction::SpliceLines {
+					idx,
+					remove,
+					lines: lines.clone(),
+				},
+			);
+		}
+		assert_matches_rebuild(
+			LYRICS,
+			EditAction::SpliceLines {
+				idx: 0,
+				remove: 4,
+				lines: Vec::new(),
+			},
+		);
+	}
+}
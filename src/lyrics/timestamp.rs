@@ -1,14 +1,70 @@
-use std::{str::FromStr, time::Duration};
+use std::{borrow::Cow, fmt, str::FromStr, time::Duration};
 
 use color_eyre::eyre::{self, OptionExt};
+use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
 
-#[derive(Debug, Default, Clone)]
+/// How many fractional digits timestamps are written with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampPrecision {
+	/// two digits, e.g. `[00:12.34]`
+	Centis,
+	/// three digits, e.g. `[00:12.345]`
+	Millis,
+	/// as many digits as each timestamp was read with
+	#[default]
+	Keep,
+}
+
+impl TimestampPrecision {
+	fn fraction_digits(self) -> Option<u8> {
+		match self {
+			TimestampPrecision::Centis => Some(2),
+			TimestampPrecision::Millis => Some(3),
+			TimestampPrecision::Keep => None,
+		}
+	}
+
+	/// Cuts off the part of `time` that would not be written, new timestamps are taken at
+	/// centisecond precision unless millis are requested
+	pub fn truncate(self, time: Duration) -> Duration {
+		let millis = time.as_millis() as u64;
+		match self {
+			TimestampPrecision::Millis => Duration::from_millis(millis),
+			TimestampPrecision::Centis | TimestampPrecision::Keep => {
+				Duration::from_millis(millis - millis % 10)
+			}
+		}
+	}
+}
+
+/// A time as written in lrc files, e.g. `00:12.34`, which is also how it is serialized
+#[derive(Debug, Clone, SerializeDisplay, DeserializeFromStr)]
 pub struct Timestamp {
 	time: Duration,
 	text: String,
+	fraction_digits: u8,
+	hours: bool,
+}
+
+impl Default for Timestamp {
+	fn default() -> Self {
+		Self::new(Duration::ZERO, 2, false)
+	}
 }
 
 impl Timestamp {
+	fn new(time: Duration, fraction_digits: u8, hours: bool) -> Self {
+		let time = Duration::from_millis(time.as_millis() as u64);
+		Self {
+			time,
+			text: Self::format_time(time, fraction_digits, hours),
+			fraction_digits,
+			hours,
+		}
+	}
+
 	pub fn time(&self) -> Duration {
 		self.time
 	}
@@ -17,18 +73,61 @@ impl Timestamp {
 		&self.text
 	}
 
-	fn format_time(m: u64, s: u64, c: u32) -> String {
-		format!("{m:0>2}:{s:0>2}.{c:0>2}")
+	/// The text with the fractional part cut or padded to the given precision
+	pub fn format(&self, precision: TimestampPrecision) -> Cow<'_, str> {
+		match precision.fraction_digits() {
+			Some(digits) if digits != self.fraction_digits => {
+				Cow::Owned(Self::format_time(self.time, digits, self.hours))
+			}
+			_ => Cow::Borrowed(&self.text),
+		}
+	}
+
+	/// A timestamp at `time` which is written in the same format as this one,
+	/// with extra fractional digits if needed to not lose precision
+	pub fn with_time(&self, time: Duration) -> Self {
+		Self::new(
+			time,
+			self.fraction_digits.max(Self::needed_digits(time)),
+			self.hours,
+		)
+	}
+
+	fn needed_digits(time: Duration) -> u8 {
+		match time.subsec_millis() {
+			millis if millis % 10 != 0 => 3,
+			millis if millis % 100 != 0 => 2,
+			_ => 1,
+		}
+	}
+
+	/// Formats as `mm:ss.xx`, switching to `h:mm:ss.xx` from 100 minutes on
+	fn format_time(time: Duration, fraction_digits: u8, hours: bool) -> String {
+		let secs = time.as_secs();
+		let fraction = time.subsec_millis() / 10u32.pow(3 - fraction_digits as u32);
+		let digits = fraction_digits as usize;
+		if hours || secs >= 100 * 60 {
+			format!(
+				"{}:{:0>2}:{:0>2}.{fraction:0>digits$}",
+				secs / 3600,
+				secs / 60 % 60,
+				secs % 60
+			)
+		} else {
+			format!("{:0>2}:{:0>2}.{fraction:0>digits$}", secs / 60, secs % 60)
+		}
 	}
 }
 
 impl From<Duration> for Timestamp {
 	fn from(time: Duration) -> Self {
-		let centis = time.subsec_millis() / 10;
-		Timestamp {
-			time: Duration::new(time.as_secs(), centis * 10_000_000),
-			text: Self::format_time(time.as_secs() / 60, time.as_secs() % 60, centis),
-		}
+		Self::new(time, Self::needed_digits(time).max(2), false)
+	}
+}
+
+impl fmt::Display for Timestamp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.text)
 	}
 }
 
@@ -36,17 +135,45 @@ impl FromStr for Timestamp {
 	type Err = eyre::ErrReport;
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		let (mm, s) = s.split_once(':').ok_or_eyre("Invalid timestamp format")?;
-		let (ss, cc) = s.split_once('.').ok_or_eyre("Invalid timestamp format")?;
-		let minutes = mm.trim().parse::<u64>()?;
-		let seconds = ss.trim().parse::<u64>()?;
-		let centis = cc.trim().parse::<u32>()?;
-		if seconds >= 60 || centis >= 100 {
+		let parse_number = |s: &str| -> eyre::Result<u64> {
+			let s = s.trim();
+			if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+				return Err(eyre::eyre!("Invalid timestamp format"));
+			}
+			Ok(s.parse()?)
+		};
+
+		let (s, fraction) = s.split_once('.').ok_or_eyre("Invalid timestamp format")?;
+		let (hh, mm, ss) = match s.split(':').collect::<Vec<_>>()[..] {
+			[mm, ss] => (None, mm, ss),
+			[hh, mm, ss] => (Some(hh), mm, ss),
+			_ => return Err(eyre::eyre!("Invalid timestamp format")),
+		};
+
+		let hours = hh.map(parse_number).transpose()?;
+		let minutes = parse_number(mm)?;
+		let seconds = parse_number(ss)?;
+		let fraction_digits = fraction.trim().len();
+		if !(1..=3).contains(&fraction_digits) {
+			return Err(eyre::eyre!("Invalid timestamp format"));
+		}
+		let millis = parse_number(fraction)? * 10u64.pow(3 - fraction_digits as u32);
+		if seconds >= 60 || (hours.is_some() && minutes >= 60) {
 			return Err(eyre::eyre!("Value out of range"));
 		};
-		let time = Duration::new(minutes * 60 + seconds, centis * 10_000_000);
-		let text = Self::format_time(minutes, seconds, centis);
-		Ok(Timestamp { time, text })
+
+		let millis = hours
+			.unwrap_or_default()
+			.checked_mul(3600)
+			.and_then(|secs| secs.checked_add(minutes.checked_mul(60)?))
+			.and_then(|secs| secs.checked_add(seconds)?.checked_mul(1000))
+			.and_then(|secs_millis| secs_millis.checked_add(millis))
+			.ok_or_eyre("Value out of range")?;
+		Ok(Self::new(
+			Duration::from_millis(millis),
+			fraction_digits as u8,
+			hours.is_some(),
+		))
 	}
 }
 
@@ -60,7 +187,7 @@ impl Eq for Timestamp {}
 
 impl PartialOrd for Timestamp {
 	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-		self.time.partial_cmp(&other.time)
+		Some(self.cmp(other))
 	}
 }
 
@@ -69,3 +196,67 @@ impl Ord for Timestamp {
 		self.time.cmp(&other.time)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(s: &str) -> Timestamp {
+		s.parse().unwrap()
+	}
+
+	#[test]
+	fn parses_fraction_digits() {
+		assert_eq!(parse("01:02.5").time(), Duration::from_millis(62_500));
+		assert_eq!(parse("01:02.50").time(), Duration::from_millis(62_500));
+		assert_eq!(parse("01:02.345").time(), Duration::from_millis(62_345));
+		assert_eq!(parse("01:02.345").text(), "01:02.345");
+		assert!("01:02".parse::<Timestamp>().is_err());
+		assert!("01:02.3456".parse::<Timestamp>().is_err());
+	}
+
+	#[test]
+	fn parses_hours() {
+		let timestamp = parse("1:02:03.45");
+		assert_eq!(timestamp.time(), Duration::from_millis(3_723_450));
+		assert_eq!(timestamp.text(), "1:02:03.45");
+		// without hours, minutes may go past 59
+		assert_eq!(parse("75:00.00").time(), Duration::from_secs(4500));
+		assert!("1:60:00.00".parse::<Timestamp>().is_err());
+		assert!("01:60.00".parse::<Timestamp>().is_err());
+	}
+
+	#[test]
+	fn rejects_overflowing_values() {
+		for s in [
+			"18446744073709551615:00.00",
+			"18446744073709551615:00:00.00",
+			"307445734561825860:00.00",
+		] {
+			let err = s.parse::<Timestamp>().unwrap_err();
+			assert_eq!(err.to_string(), "Value out of range", "{s}");
+		}
+	}
+
+	#[test]
+	fn formats_with_precision() {
+		let timestamp = parse("01:02.345");
+		assert_eq!(timestamp.format(TimestampPrecision::Centis), "01:02.34");
+		assert_eq!(timestamp.format(TimestampPrecision::Keep), "01:02.345");
+		assert_eq!(
+			parse("01:02.3").format(TimestampPrecision::Millis),
+			"01:02.300"
+		);
+	}
+
+	#[test]
+	fn serializes_as_text() {
+		let timestamp = parse("1:02:03.4");
+		let json = serde_json::to_string(&timestamp).unwrap();
+		assert_eq!(json, r#""1:02:03.4""#);
+		let read_back: Timestamp = serde_json::from_str(&json).unwrap();
+		assert_eq!(read_back.text(), timestamp.text());
+		assert_eq!(read_back.time(), timestamp.time());
+		assert!(serde_json::from_str::<Timestamp>(r#""00:01.2345""#).is_err());
+	}
+}
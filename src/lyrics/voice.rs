@@ -0,0 +1,77 @@
+use std::{fmt, str::FromStr};
+
+use color_eyre::eyre;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+
+/// The singer of a line, written as a prefix to the line text, e.g. `v1: text` or `F: text`
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub enum Voice {
+	Numbered(u16),
+	Female,
+	Male,
+	Duet,
+}
+
+impl Voice {
+	/// Splits a voice prefix off the start of the text, if there is one
+	pub fn strip_prefix(s: &str) -> (Option<Self>, &str) {
+		let Some((prefix, rest)) = s.split_once(':') else {
+			return (None, s);
+		};
+		match prefix.parse() {
+			Ok(voice) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+				(Some(voice), rest.trim_start())
+			}
+			_ => (None, s),
+		}
+	}
+
+	/// The voice after `voice` when cycling through voices, numbered voices go from 1 to 2 and back
+	/// to none, the F/M/D voices go from female to male to duet
+	pub fn cycle(voice: Option<Self>, gendered: bool) -> Option<Self> {
+		match (voice, gendered) {
+			(None, false) => Some(Voice::Numbered(1)),
+			(None, true) => Some(Voice::Female),
+			(Some(Voice::Numbered(1)), _) => Some(Voice::Numbered(2)),
+			(Some(Voice::Numbered(_)), _) => None,
+			(Some(Voice::Female), _) => Some(Voice::Male),
+			(Some(Voice::Male), _) => Some(Voice::Duet),
+			(Some(Voice::Duet), _) => None,
+		}
+	}
+
+	pub fn is_gendered(self) -> bool {
+		!matches!(self, Voice::Numbered(_))
+	}
+}
+
+impl fmt::Display for Voice {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Voice::Numbered(n) => write!(f, "v{n}"),
+			Voice::Female => f.write_str("F"),
+			Voice::Male => f.write_str("M"),
+			Voice::Duet => f.write_str("D"),
+		}
+	}
+}
+
+impl FromStr for Voice {
+	type Err = eyre::ErrReport;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"F" => Ok(Voice::Female),
+			"M" => Ok(Voice::Male),
+			"D" => Ok(Voice::Duet),
+			s => match s.strip_prefix('v').map(str::parse) {
+				Some(Ok(n)) if n > 0 && s[1..].bytes().all(|b| b.is_ascii_digit()) => {
+					Ok(Voice::Numbered(n))
+				}
+				_ => Err(eyre::eyre!("Invalid voice")),
+			},
+		}
+	}
+}
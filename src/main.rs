@@ -5,9 +5,13 @@ use std::{
 
 use clap::Parser;
 use cli::Args;
-use color_eyre::{Result, eyre::OptionExt};
+use color_eyre::{
+	Result,
+	eyre::{self, OptionExt},
+};
 use directories::ProjectDirs;
 use directories::UserDirs;
+use lyrics::{FileEncoding, LyricsFormat};
 use song::Song;
 use state::{AppState, Config};
 use tui::{App, View};
@@ -31,10 +35,8 @@ async fn main() -> Result<()> {
 	let project_dirs = ProjectDirs::from("", "LunaPresent", "lrxed");
 	let config_dir = if let Some(project_dirs) = &project_dirs {
 		Some(project_dirs.config_dir())
-	} else if let Some(home_dir) = user_dirs.as_ref().map(|x| x.home_dir()) {
-		Some(home_dir)
 	} else {
-		None
+		user_dirs.as_ref().map(|x| x.home_dir())
 	};
 
 	if let Some(file_type) = args.print_config_path {
@@ -110,6 +112,10 @@ async fn main() -> Result<()> {
 		return Ok(());
 	}
 
+	if let Some([input, output]) = args.convert.as_deref() {
+		return convert(input, output, &config);
+	}
+
 	let mut path = if let Some(path) = args.path {
 		path
 	} else if let Some(path) = &config.settings.default_path {
@@ -120,34 +126,63 @@ async fn main() -> Result<()> {
 		PathBuf::from("/")
 	};
 
-	if let Ok(remaining) = path.strip_prefix("~") {
-		if let Some(home_dir) = user_dirs.as_ref().map(|dirs| dirs.home_dir()) {
-			path = home_dir.join(remaining);
-		}
+	if let Ok(remaining) = path.strip_prefix("~")
+		&& let Some(home_dir) = user_dirs.as_ref().map(|dirs| dirs.home_dir())
+	{
+		path = home_dir.join(remaining);
 	}
 
-	let mut state: AppState;
+	let mut state = AppState::new(if path.is_file() {
+		View::Editor
+	} else {
+		View::FileTree
+	});
+	state.config = config;
+	state.data_dir = project_dirs.map(|dirs| dirs.data_dir().to_owned());
 
 	if path.is_file() {
 		let song = Song::from_file(&path)?;
-		state = AppState::new(View::Editor);
 
-		state.audio.audio_player = Some(state.audio.audio_device.try_play(song.mp3_file)?);
+		state.audio.audio_player = Some(state.audio.audio_device.try_play(song.mp3_file.clone())?);
 
-		state.song.load_file_if_exists(song.lrc_file)?;
+		state
+			.song
+			.load_from_song(song, state.history_dir(), &state.config.settings)?;
+		state.report_diagnostics();
 	} else {
-		state = AppState::new(View::FileTree);
 		state.file_browser.open_directory(&path)?;
 	}
 
-	state.config = config;
-
 	let terminal = ratatui::init();
 	let app_result = App.run(terminal, &mut state).await;
 	ratatui::restore();
 	app_result
 }
 
+fn convert(input: &Path, output: &Path, config: &Config) -> Result<()> {
+	let input_format = LyricsFormat::from_path(input).ok_or_eyre("Unknown input format")?;
+	let output_format = LyricsFormat::from_path(output).ok_or_eyre("Unknown output format")?;
+	if input == output {
+		return Err(eyre::eyre!("Input and output are the same file"));
+	}
+
+	let (text, _) = FileEncoding::read(input)?;
+	let (mut lyrics, diagnostics) = input_format.read_with_diagnostics(text.as_bytes())?;
+	for diagnostic in diagnostics {
+		eprintln!("{}: {diagnostic}", input.display());
+	}
+	if config.settings.inline_translations {
+		lyrics.bind_inline_translations();
+	}
+	output_format.write(
+		&lyrics,
+		&mut io::BufWriter::new(fs::File::create(output)?),
+		&config.settings.write_options(
+			Song::find_audio_file(input).and_then(|path| Song::audio_duration(&path)),
+		),
+	)
+}
+
 fn first_existing_file<P>(directory: &Path, file_names: &[&P]) -> Option<PathBuf>
 where
 	P: AsRef<Path>,
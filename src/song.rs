@@ -1,16 +1,16 @@
-use crate::lyrics::Lyrics;
+use crate::lyrics::{Diagnostic, FileEncoding, Lyrics, LyricsFormat, embedded};
 use thiserror::Error;
 
 use std::{
 	convert::identity,
 	fmt::Debug,
-	fs::File,
-	io::BufReader,
+	fs,
 	path::{Path, PathBuf},
+	time::Duration,
 };
 
 use lofty::{
-	file::TaggedFileExt,
+	file::{AudioFile, TaggedFileExt},
 	tag::{ItemKey, Tag, TagType},
 };
 
@@ -30,16 +30,19 @@ pub enum LoadSongError {
 pub struct SongMeta {
 	pub title: String,
 	pub artist: String,
+	pub album: String,
 }
 
 impl From<Tag> for SongMeta {
 	fn from(value: Tag) -> Self {
 		let title = value.get_string(&ItemKey::TrackTitle).unwrap_or_default();
 		let artist = value.get_string(&ItemKey::TrackArtist).unwrap_or_default();
+		let album = value.get_string(&ItemKey::AlbumTitle).unwrap_or_default();
 
 		Self {
 			title: title.to_string(),
 			artist: artist.to_string(),
+			album: album.to_string(),
 		}
 	}
 }
@@ -50,7 +53,13 @@ pub struct Song {
 	pub meta: Option<SongMeta>,
 	pub lrc_file: PathBuf,
 	pub lyrics: Lyrics,
+	/// malformed parts of the lyrics file
+	pub diagnostics: Vec<Diagnostic>,
+	/// encoding the lyrics file was read in
+	pub encoding: FileEncoding,
 	pub has_file: bool,
+	/// whether [`Song::open`] has read the rest of the lyrics
+	opened: bool,
 }
 
 impl Song {
@@ -70,50 +79,201 @@ impl Song {
 			return Err(LoadSongError::PathWasDirectory);
 		}
 
-		if Self::is_valid_file_type(&path) {
+		if Self::is_valid_file_type(path) {
 			Self::from_mp3(path)
 		} else {
 			Err(LoadSongError::InvalidFileType)
 		}
 	}
 
-	fn new(mp3_file: PathBuf, lrc_file: PathBuf) -> Result<Song, LoadSongError> {
+	fn new(mp3_file: PathBuf, mut lrc_file: PathBuf) -> Result<Song, LoadSongError> {
 		let meta = lofty::read_from_path(&mp3_file)
-			.map(|tags| tags.tag(TagType::Id3v2).cloned())
+			.map(|tags| {
+				tags.tag(TagType::Id3v2)
+					.or(tags.primary_tag())
+					.or(tags.first_tag())
+					.cloned()
+			})
 			.map_or(None, identity)
 			.map(SongMeta::from);
 
-		let (lyrics, has_file) = if let Ok(file) = File::open(&lrc_file) {
-			let reader = BufReader::new(file);
-			let mut result = Lyrics::default();
+		let mut encoding = FileEncoding::default();
+		let (lyrics, diagnostics, has_file) =
+			if let Ok((text, file_encoding)) = FileEncoding::read(&lrc_file) {
+				let format = LyricsFormat::from_path(&lrc_file).unwrap_or(LyricsFormat::Lrc);
 
-			if result.read_overwrite(reader).is_err() {
-				return Err(LoadSongError::FailedToReadLyrics);
+				match format.read_with_diagnostics(text.as_bytes()) {
+					Ok((lyrics, diagnostics)) => {
+						encoding = file_encoding;
+						(lyrics, diagnostics, true)
+					}
+					// a .json file next to the audio file is likely something else than lyrics,
+					// and it is the last file looked for
+					Err(_) if format == LyricsFormat::Json => {
+						lrc_file.set_extension("lrc");
+						(Lyrics::default(), Vec::new(), false)
+					}
+					Err(_) => return Err(LoadSongError::FailedToReadLyrics),
+				}
 			} else {
-				(result, true)
-			}
-		} else {
-			(Lyrics::default(), false)
-		};
+				(Lyrics::default(), Vec::new(), false)
+			};
 
 		Ok(Self {
 			meta,
 			mp3_file,
 			lrc_file,
 			lyrics,
+			diagnostics,
+			encoding,
 			has_file,
+			opened: false,
 		})
 	}
 
+	/// Reads the parts of the lyrics the file browser does not need, once the song is opened: the
+	/// lyrics embedded in the audio file if there is no lyrics file, the lines following a line
+	/// with the same timestamp as its translation with `inline_translations`, and the translation
+	/// and romanisation files next to the lyrics file
+	pub fn open(&mut self, inline_translations: bool) {
+		if self.opened {
+			return;
+		}
+		self.opened = true;
+
+		if !self.has_file
+			&& let Ok(Some(lyrics)) = embedded::read(&self.mp3_file)
+		{
+			self.lyrics = lyrics;
+			self.has_file = true;
+		}
+		if inline_translations {
+			self.lyrics.bind_inline_translations();
+		}
+
+		for (lang, path) in Self::find_secondary_files(&self.lrc_file) {
+			if let Ok(secondary) = FileEncoding::read(&path)
+				.map_err(Into::into)
+				.and_then(|(text, _)| LyricsFormat::Lrc.read(text.as_bytes()))
+			{
+				self.lyrics.attach_secondary_lyrics(&lang, secondary);
+			}
+		}
+	}
+
 	fn from_mp3(path: &Path) -> Result<Song, LoadSongError> {
 		let lrc_path = if path.with_extension("lrc").exists() {
 			path.with_extension("lrc")
 		} else if path.with_extension("txt").exists() {
 			path.with_extension("txt")
+		} else if path.with_extension("ttml").exists() {
+			path.with_extension("ttml")
+		} else if path.with_extension("srt").exists() {
+			path.with_extension("srt")
+		} else if path.with_extension("json").exists() {
+			path.with_extension("json")
 		} else {
 			path.with_extension("lrc")
 		};
 
 		Self::new(path.into(), lrc_path)
 	}
+
+	/// The audio file next to a lyrics file, with the same name stem
+	pub fn find_audio_file(lyrics_path: &Path) -> Option<PathBuf> {
+		["mp3", "wav", "flac", "ogg"]
+			.into_iter()
+			.map(|ext| lyrics_path.with_extension(ext))
+			.find(|path| path.is_file())
+	}
+
+	/// The translation and romanisation files next to a lyrics file, named `song.<lang>.lrc`, as
+	/// (language, path). A file with an audio file of its own, like `song.remix.lrc` next to
+	/// `song.remix.mp3`, holds the lyrics of that song instead.
+	pub fn find_secondary_files(lyrics_path: &Path) -> Vec<(String, PathBuf)> {
+		let (Some(parent), Some(stem)) = (
+			lyrics_path.parent(),
+			lyrics_path.file_stem().and_then(|stem| stem.to_str()),
+		) else {
+			return Vec::new();
+		};
+		let dir = if parent.as_os_str().is_empty() {
+			Path::new(".")
+		} else {
+			parent
+		};
+		let Ok(entries) = fs::read_dir(dir) else {
+			return Vec::new();
+		};
+
+		let mut files: Vec<_> = entries
+			.filter_map(|entry| {
+				let path = entry.ok()?.path();
+				let lang = path
+					.file_name()?
+					.to_str()?
+					.strip_prefix(stem)?
+					.strip_prefix('.')?
+					.strip_suffix(".lrc")?
+					.to_owned();
+				let is_lang = !lang.is_empty()
+					&& lang
+						.chars()
+						.all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+				(is_lang && path.is_file() && Self::find_audio_file(&path).is_none())
+					.then_some((lang, path))
+			})
+			.collect();
+		files.sort();
+		files
+	}
+
+	/// Path of the translation or romanisation file for the given language
+	pub fn secondary_file(&self, lang: &str) -> PathBuf {
+		self.lrc_file.with_extension(format!("{lang}.lrc"))
+	}
+
+	pub fn audio_duration(path: &Path) -> Option<Duration> {
+		lofty::read_from_path(path)
+			.ok()
+			.map(|file| file.properties().duration())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn finds_secondary_files_without_their_own_audio() {
+		let dir =
+			std::env::temp_dir().join(format!("lrxed-{}-secondary-files", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		for file in [
+			"Track.mp3",
+			"Track.lrc",
+			"Track.en.lrc",
+			"Track.ja-Latn.lrc",
+			"Track.remix.lrc",
+			"Track.remix.mp3",
+			"Track.live.lrc",
+			"Track.live.flac",
+			"Track.notes.txt",
+			"Track.a b.lrc",
+			"Other.de.lrc",
+		] {
+			fs::write(dir.join(file), "").unwrap();
+		}
+
+		let files = Song::find_secondary_files(&dir.join("Track.lrc"));
+		assert_eq!(
+			files,
+			[
+				("en".to_owned(), dir.join("Track.en.lrc")),
+				("ja-Latn".to_owned(), dir.join("Track.ja-Latn.lrc")),
+			]
+		);
+		fs::remove_dir_all(dir).unwrap();
+	}
 }
@@ -1,16 +1,28 @@
-use super::{AudioState, Config, FileBrowserState, ModalState, SongState, ToastState};
-use std::{ffi::OsString, io::stdout};
+use super::{AudioState, Config, FileBrowserState, ModalState, SongState, ToastKind, ToastState};
+use std::{
+	ffi::OsString,
+	io::stdout,
+	path::{Path, PathBuf},
+};
 
 use color_eyre::eyre;
 use edit::Builder;
-use ratatui::crossterm::{
-	ExecutableCommand,
-	terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+use ratatui::{
+	crossterm::{
+		ExecutableCommand,
+		terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+	},
+	layout::Position,
 };
 
 use crate::{
-	lyrics::editing::{Edit, EditAction},
-	tui::{Cursor, Modal, View},
+	config::SaveTarget,
+	lyrics::{
+		LyricLine, LyricsFormat, Severity, TimestampPrecision,
+		editing::{Edit, StretchAnchor},
+		lint::TrackInfo,
+	},
+	tui::{Cursor, EditorMode, Modal, View},
 };
 
 pub struct AppState {
@@ -21,8 +33,15 @@ pub struct AppState {
 	pub cursor: Cursor,
 	pub config: Config,
 	pub active_view: View,
+	pub editor_mode: EditorMode,
+	/// lines whose timestamps were copied, to be pasted onto others
+	pub copied_lines: Vec<LyricLine>,
+	/// the last two lines marked to time-stretch the lyrics with
+	pub stretch_anchors: Vec<StretchAnchor>,
 	pub active_modal: Option<Modal>,
 	pub toasts: ToastState,
+	/// the application data directory, if there is one
+	pub data_dir: Option<PathBuf>,
 	pub refresh_term: bool,
 	pub should_go_back: bool,
 	pub should_quit: bool,
@@ -38,17 +57,116 @@ impl AppState {
 			cursor: Default::default(),
 			config: Default::default(),
 			active_view: initial_view,
+			editor_mode: EditorMode::Normal,
+			copied_lines: Vec::new(),
+			stretch_anchors: Vec::new(),
 			active_modal: None,
 			toasts: Default::default(),
+			data_dir: None,
 			refresh_term: false,
 			should_quit: false,
 			should_go_back: false,
 		}
 	}
 
+	/// Shows the malformed parts of the opened lyrics file as toasts, and which file the lyrics are
+	/// saved to if it is not the one they were read from
+	pub fn report_diagnostics(&mut self) {
+		const MAX_TOASTS: usize = 3;
+
+		let song = &self.song.song;
+		if song.has_file
+			&& self.config.settings.save_target != SaveTarget::Embedded
+			&& song.lrc_file.extension().is_some_and(|ext| ext != "lrc")
+		{
+			let file_name =
+				|path: &Path| path.file_name().unwrap_or_default().display().to_string();
+			self.toasts.push_warning(format!(
+				"Lyrics read from {} are saved to {}",
+				file_name(&song.lrc_file),
+				file_name(&song.lrc_file.with_extension("lrc"))
+			));
+		}
+
+		let diagnostics = &self.song.song.diagnostics;
+		if diagnostics.len() > MAX_TOASTS {
+			self.toasts.push_warning(format!(
+				"{} more problems in the lyrics file",
+				diagnostics.len() - MAX_TOASTS
+			));
+		}
+		// toasts are shown newest first
+		for diagnostic in diagnostics.iter().take(MAX_TOASTS).rev() {
+			let kind = match diagnostic.severity {
+				Severity::Error => ToastKind::Error,
+				Severity::Warning => ToastKind::Warning,
+			};
+			self.toasts.push_kind(diagnostic.to_string(), kind);
+		}
+	}
+
+	/// Where undo histories are kept, unless they are not
+	pub fn history_dir(&self) -> Option<PathBuf> {
+		self.data_dir
+			.as_ref()
+			.filter(|_| self.config.settings.persistent_history)
+			.map(|dir| dir.join("history"))
+	}
+
+	/// The audio file information the lyrics are checked against
+	pub fn track_info(&self) -> TrackInfo {
+		let meta = self.song.song.meta.as_ref();
+		TrackInfo {
+			duration: self.audio.audio_player.as_ref().map(|x| x.duration()),
+			title: meta.map(|meta| meta.title.clone()),
+			artist: meta.map(|meta| meta.artist.clone()),
+			album: meta.map(|meta| meta.album.clone()),
+		}
+	}
+
+	/// Saves the lyrics, releasing the audio file while they are written to its tags
+	pub fn save_song(&mut self) -> eyre::Result<()> {
+		let track = self.track_info();
+		let settings = &self.config.settings;
+		match &self.audio.audio_player {
+			Some(player) if settings.save_target != SaveTarget::Sidecar => {
+				player.with_released(|| self.song.write_to_file(settings, &track))?
+			}
+			_ => self.song.write_to_file(settings, &track),
+		}
+	}
+
+	/// Exports the lyrics of the open song next to its lyrics file
+	pub fn export_lyrics(&self, format: LyricsFormat) -> eyre::Result<()> {
+		self.song.export_lyrics(
+			format,
+			&self.config.settings,
+			self.audio.audio_player.as_ref().map(|x| x.duration()),
+		)
+	}
+
+	/// Moves the editor cursor to `pos` and scrolls it into view
+	pub fn jump_cursor(&mut self, pos: Position) {
+		let lyrics = &self.song.song.lyrics;
+		self.cursor
+			.set_y(pos.y)
+			.set_x(pos.x)
+			.update_pos(lyrics.line_widths())
+			.update_scroll(
+				Position::new(
+					lyrics.line_widths().max().unwrap_or_default(),
+					lyrics.line_count(),
+				),
+				self.config.settings.scrolloff,
+			);
+	}
+
 	pub fn open_in_editor(&mut self) -> eyre::Result<()> {
 		let mut buf = Vec::new();
-		self.song.song.lyrics.write_to(&mut buf)?;
+		self.song
+			.song
+			.lyrics
+			.write_to(&mut buf, TimestampPrecision::Keep)?;
 		stdout().execute(LeaveAlternateScreen)?;
 
 		let bytes = edit::edit_bytes_with_builder(
@@ -72,16 +190,18 @@ impl AppState {
 			return Ok(());
 		}
 
-		let edit = Edit::new(
-			EditAction::RestoreState(bytes),
-			EditAction::RestoreState(buf),
-		);
-
-		let result = edit.execute_forwards(&mut self.song.song.lyrics, &mut self.song.time_index);
+		let mut lyrics = self.song.song.lyrics.clone();
+		lyrics.read_overwrite(&bytes[..], self.config.settings.inline_translations)?;
+		let Some(edit) = Edit::between(&self.song.song.lyrics, &lyrics) else {
+			return Ok(());
+		};
+		edit.execute_forwards(&mut self.song.song.lyrics, &mut self.song.time_index)?;
 
-		self.song.history.push(edit);
+		self.song
+			.history
+			.push(edit, "edit in external editor".to_owned());
 		self.song.changed = true;
 
-		result
+		Ok(())
 	}
 }
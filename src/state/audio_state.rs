@@ -13,7 +13,7 @@ pub struct AudioState {
 impl AudioState {
 	pub fn seek_relative(&self, relative_position: f32) -> eyre::Result<Option<Duration>> {
 		if let Some(player) = self.audio_player.as_ref() {
-			let target_pos = player.duration().mul_f32(relative_position.min(1.).max(0.));
+			let target_pos = player.duration().mul_f32(relative_position.clamp(0., 1.));
 			player.seek(target_pos)?;
 			Ok(Some(target_pos))
 		} else {
@@ -20,7 +20,7 @@ pub enum FileBrowserItem {
 }
 
 impl FileBrowserItem {
-	pub fn name(&self) -> Cow<str> {
+	pub fn name(&self) -> Cow<'_, str> {
 		match self {
 			Self::Directory(path) => Cow::Borrowed(
 				path.file_name()
@@ -110,11 +110,11 @@ impl FileBrowserState {
 						let mut result = directory
 							.filter_map(|item| item.map_or(None, |r| Some(r.path())))
 							.map(|path| FileBrowserItem::try_from(path.as_path()))
-							.filter_map(|result| result.map_or(None, Some))
+							.filter_map(|result| result.ok())
 							.collect::<Vec<_>>();
 
 						result.sort();
-						result.into()
+						result
 					}
 					Err(_) => vec![],
 				}
@@ -12,4 +12,4 @@ pub use config::Config;
 pub use file_browser_state::{FileBrowserItem, FileBrowserState};
 pub use modal_state::{ConfirmBoxAction, ModalState};
 pub use song_state::SongState;
-pub use toast_state::ToastState;
+pub use toast_state::{ToastKind, ToastState};
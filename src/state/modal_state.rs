@@ -1,3 +1,5 @@
+use crate::lyrics::lint::Problem;
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum ConfirmBoxAction {
 	#[default]
@@ -24,9 +26,14 @@ impl ConfirmBoxAction {
 	}
 }
 
+pub type KeysViewCache = Vec<(String, Vec<(String, String)>)>;
+
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct ModalState {
 	pub confirm_box_selected: ConfirmBoxAction,
 	pub popup_scroll: u16,
-	pub keys_view_cache: Option<Vec<(String, Vec<(String, String)>)>>,
+	pub keys_view_cache: Option<KeysViewCache>,
+	pub problems: Vec<Problem>,
+	pub problems_selected: usize,
+	pub history_selected: usize,
 }
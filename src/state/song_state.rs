@@ -1,17 +1,23 @@
-use color_eyre::eyre;
+use color_eyre::eyre::{self, OptionExt, WrapErr};
 use ratatui::layout::Position;
 
 use std::{
-	fs::{self, File, OpenOptions},
-	io::{BufReader, BufWriter},
-	path::PathBuf,
+	borrow::Cow,
+	fs::{self, File},
+	io::BufWriter,
+	ops::{Range, RangeInclusive},
+	path::{Path, PathBuf},
 	time::Duration,
 };
 
 use crate::{
+	config::{SaveTarget, Settings, TagPolicy},
 	lyrics::{
-		Lyrics, TimeIndex, TimeIndexHint,
-		editing::{Edit, EditAction, History},
+		FileEncoding, LyricLine, Lyrics, LyricsFormat, TagType, TimeIndex, TimeIndexHint,
+		Timestamp, TimestampPrecision, Voice, WriteOptions,
+		editing::{Edit, EditAction, History, Interpolation, SplitTimestamp, TimeStretch},
+		embedded,
+		lint::{self, TrackInfo},
 	},
 	song::Song,
 };
@@ -23,63 +29,198 @@ pub struct SongState {
 	pub time_index_hint: TimeIndexHint,
 	pub history: History,
 	pub changed: bool,
+	/// where the undo history is kept after the song is closed, if it is
+	pub history_dir: Option<PathBuf>,
 }
 
 impl SongState {
-	pub fn load_from_song(&mut self, song: Song) -> eyre::Result<bool> {
+	/// Opens a song, along with the undo history it was saved with if it is kept in `history_dir`
+	pub fn load_from_song(
+		&mut self,
+		song: Song,
+		history_dir: Option<PathBuf>,
+		settings: &Settings,
+	) -> eyre::Result<bool> {
 		self.song = song;
-		self.time_index = TimeIndex::new(self.song.lyrics.lines().iter());
+		self.song.open(settings.inline_translations);
+		self.time_index = TimeIndex::new(&self.song.lyrics);
 		self.time_index_hint = TimeIndexHint::default();
+		self.history = history_dir
+			.as_ref()
+			.filter(|_| self.song.has_file)
+			.and_then(|dir| History::load(dir, &self.song.lrc_file, &self.song.lyrics))
+			.unwrap_or_default();
+		self.history_dir = history_dir;
 
 		Ok(true)
 	}
 
-	pub fn load_file_if_exists(&mut self, lrc_path: PathBuf) -> eyre::Result<bool> {
-		let exists = if lrc_path.exists() {
-			self.song
-				.lyrics
-				.read_overwrite(BufReader::new(File::open(&lrc_path)?))?;
+	/// Writes the lyrics to the lyrics file and/or the audio file tags, depending on the save target
+	pub fn write_to_file(&mut self, settings: &Settings, track: &TrackInfo) -> eyre::Result<()> {
+		self.fill_auto_tags(settings, track)?;
 
-			true
+		let lyrics = if settings.compress_repeated_lines_on_save {
+			let mut lyrics = self.song.lyrics.clone();
+			lyrics.compress_repeats();
+			Cow::Owned(lyrics)
 		} else {
-			self.song.lyrics = Lyrics::default();
-			false
+			Cow::Borrowed(&self.song.lyrics)
 		};
-		self.time_index = TimeIndex::new(self.song.lyrics.lines().iter());
-		self.time_index_hint = TimeIndexHint::default();
-		self.song.lrc_file = lrc_path;
-		Ok(exists)
-	}
+		let options = settings.write_options(track.duration);
 
-	pub fn write_to_file(&mut self, replace_txt_file: bool) -> eyre::Result<()> {
-		if self
-			.song
-			.lrc_file
-			.extension()
-			.is_some_and(|ext| ext == "txt")
+		if settings.save_target != SaveTarget::Sidecar {
+			embedded::write(&lyrics, &self.song.mp3_file, &options)?;
+		}
+		if settings.save_target != SaveTarget::Embedded {
+			self.song.encoding = Self::write_sidecar(
+				&mut self.song.lrc_file,
+				&lyrics,
+				settings.save_encoding.apply(self.song.encoding),
+				settings,
+				&options,
+			)?;
+			for lang in lyrics.secondary_langs() {
+				Self::write_secondary_file(
+					&self.song.secondary_file(lang),
+					&lyrics,
+					lang,
+					settings,
+					&options,
+				)?;
+			}
+		}
+
+		self.song.has_file = true;
+		self.changed = false;
+
+		if let Some(dir) = &self.history_dir
+			&& settings.save_target != SaveTarget::Embedded
 		{
-			let new_file_path = self.song.lrc_file.with_extension("lrc");
+			// the history is saved at the lyrics as written, which they are read back as
+			let mut history = Cow::Borrowed(&self.history);
+			if let Some(edit) = Edit::between(&self.song.lyrics, &lyrics) {
+				history
+					.to_mut()
+					.push(edit, "compress repeated lines".to_owned());
+			}
+			history
+				.save(dir, &self.song.lrc_file, &lyrics, options.precision)
+				.wrap_err("Lyrics saved, but not their undo history")?;
+		}
+
+		Ok(())
+	}
+
+	/// Fills in the header tags configured in `auto-tags`, as a single undoable edit
+	fn fill_auto_tags(&mut self, settings: &Settings, track: &TrackInfo) -> eyre::Result<()> {
+		let tags = settings
+			.auto_tags
+			.values(track, settings.lyrics_by.as_deref());
+		if tags.is_empty() {
+			return Ok(());
+		}
+
+		self.transform_lyrics("fill in header tags", |lyrics| {
+			for (tag, policy, value) in tags {
+				let update = match lyrics.tag(&tag) {
+					Some(existing) => policy == TagPolicy::Always && existing != value,
+					None => true,
+				};
+				if update {
+					lyrics.set_tag(tag, Some(value));
+				}
+			}
+		})
+	}
+
+	/// Writes the lyrics to a .lrc file, since the other formats can't hold unsynced lines, tags or
+	/// repeat timestamps. Lyrics read from another format are saved next to that file, which is
+	/// kept unless it is a .txt file and `replace-txt-file-on-save` is set. Returns the encoding
+	/// the file was written in.
+	fn write_sidecar(
+		lrc_file: &mut PathBuf,
+		lyrics: &Lyrics,
+		encoding: FileEncoding,
+		settings: &Settings,
+		options: &WriteOptions,
+	) -> eyre::Result<FileEncoding> {
+		if lrc_file.extension().is_none_or(|ext| ext != "lrc") {
+			let new_file_path = lrc_file.with_extension("lrc");
 
-			if replace_txt_file {
-				fs::rename(&self.song.lrc_file, &new_file_path)?;
+			if settings.replace_txt_file_on_save
+				&& lrc_file.extension().is_some_and(|ext| ext == "txt")
+			{
+				fs::rename(&lrc_file, &new_file_path)?;
 			}
 
-			self.song.lrc_file = new_file_path;
+			*lrc_file = new_file_path;
 		}
 
-		self.song.lyrics.write_to(&mut BufWriter::new(
-			OpenOptions::new()
-				.read(false)
-				.write(true)
-				.create(true)
-				.truncate(true)
-				.open(&self.song.lrc_file)?,
-		))?;
+		let mut text = Vec::new();
+		lyrics.write_to(&mut text, options.precision)?;
+		let bytes = encoding.encode(&String::from_utf8(text)?)?;
+		fs::write(lrc_file, bytes)?;
 
-		self.song.has_file = true;
-		self.changed = false;
+		Ok(encoding)
+	}
 
-		Ok(())
+	/// Writes the lines read from a translation or romanisation file back to it, with the tags it
+	/// had
+	fn write_secondary_file(
+		path: &Path,
+		lyrics: &Lyrics,
+		lang: &str,
+		settings: &Settings,
+		options: &WriteOptions,
+	) -> eyre::Result<()> {
+		let (mut secondary, encoding) = match FileEncoding::read(path) {
+			Ok((text, encoding)) => (LyricsFormat::Lrc.read(text.as_bytes())?, encoding),
+			Err(_) => Default::default(),
+		};
+		secondary.set_lines(lyrics.secondary_lines(lang));
+
+		let mut text = Vec::new();
+		secondary.write_to(&mut text, options.precision)?;
+		let bytes = settings
+			.save_encoding
+			.apply(encoding)
+			.encode(&String::from_utf8(text)?)?;
+		Ok(fs::write(path, bytes)?)
+	}
+
+	/// The file next to the lyrics file the lyrics are exported to in the given format
+	pub fn export_path(&self, format: LyricsFormat) -> PathBuf {
+		self.song.lrc_file.with_extension(format.extension())
+	}
+
+	/// Writes the lyrics next to the lyrics file, in the given format
+	pub fn export_lyrics(
+		&self,
+		format: LyricsFormat,
+		settings: &Settings,
+		duration: Option<Duration>,
+	) -> eyre::Result<()> {
+		let path = self.export_path(format);
+		eyre::ensure!(
+			path != self.song.lrc_file,
+			"The lyrics were read from this .{} file, exporting would overwrite it",
+			format.extension()
+		);
+		format.write(
+			&self.song.lyrics,
+			&mut BufWriter::new(File::create(path)?),
+			&settings.write_options(duration),
+		)
+	}
+
+	/// Replaces the lyrics with the file of the given format next to the lyrics file
+	pub fn import_lyrics(&mut self, format: LyricsFormat) -> eyre::Result<()> {
+		let path = self.song.lrc_file.with_extension(format.extension());
+		let (text, _) = FileEncoding::read(&path)?;
+		let lyrics = format.read(text.as_bytes())?;
+		self.transform_lyrics(&format!("import .{} file", format.extension()), |x| {
+			*x = lyrics
+		})
 	}
 
 	pub fn undo(&mut self) -> eyre::Result<()> {
@@ -96,6 +237,20 @@ impl SongState {
 			.redo(&mut self.song.lyrics, &mut self.time_index)
 	}
 
+	/// Undoes and redoes edits until the lyrics are at the given edit of the history, or as they
+	/// were opened for `None`
+	pub fn jump_to_history(&mut self, entry: Option<usize>) -> eyre::Result<()> {
+		let before = self.history.current();
+		let result = self
+			.history
+			.jump_to(entry, &mut self.song.lyrics, &mut self.time_index);
+		if self.history.current() != before {
+			self.changed = true;
+		}
+
+		result
+	}
+
 	pub fn set_timestamp(
 		&mut self,
 		cursor_pos: Position,
@@ -104,7 +259,7 @@ impl SongState {
 		let prev_val = self
 			.song
 			.lyrics
-			.time_at_cursor(cursor_pos.x, cursor_pos.y)
+			.time_at_line(cursor_pos.y)
 			.map(|x| x.time());
 		let edit = Edit::new(
 			EditAction::SetTimestamp {
@@ -117,9 +272,708 @@ impl SongState {
 			},
 		);
 		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
-		self.history.push(edit);
+		self.history.push(
+			edit,
+			describe_timestamp(&format!("line {}", cursor_pos.y + 1), timestamp),
+		);
+		self.changed = true;
+
+		Ok(())
+	}
+
+	pub fn set_word_timestamp(
+		&mut self,
+		cursor_pos: Position,
+		timestamp: Option<Duration>,
+	) -> eyre::Result<()> {
+		let line = self
+			.song
+			.lyrics
+			.lines()
+			.get(cursor_pos.y as usize)
+			.ok_or_eyre("Line index out of range")?;
+		let word = line.word_at(cursor_pos.x).ok_or_eyre("No word at cursor")?;
+		let prev_val = line.word_timestamp(word).map(|x| x.time());
+		let edit = Edit::new(
+			EditAction::SetWordTimestamp {
+				idx: cursor_pos.y,
+				word,
+				timestamp,
+			},
+			EditAction::SetWordTimestamp {
+				idx: cursor_pos.y,
+				word,
+				timestamp: prev_val,
+			},
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history.push(
+			edit,
+			describe_timestamp(&format!("word on line {}", cursor_pos.y + 1), timestamp),
+		);
+		self.changed = true;
+
+		Ok(())
+	}
+
+	/// Sets the timestamp of the word under the cursor if it has one, otherwise of the line
+	pub fn set_timestamp_at_cursor(
+		&mut self,
+		cursor_pos: Position,
+		timestamp: Option<Duration>,
+	) -> eyre::Result<()> {
+		if self
+			.song
+			.lyrics
+			.word_timestamp_at_cursor(cursor_pos.x, cursor_pos.y)
+			.is_some()
+		{
+			self.set_word_timestamp(cursor_pos, timestamp)
+		} else {
+			self.set_timestamp(cursor_pos, timestamp)
+		}
+	}
+
+	pub fn set_offset(&mut self, offset: Option<i64>) -> eyre::Result<()> {
+		let prev_val = self
+			.song
+			.lyrics
+			.metadata()
+			.iter()
+			.any(|meta| *meta.tag_type() == TagType::Offset)
+			.then(|| self.song.lyrics.offset());
+		let edit = Edit::new(
+			EditAction::SetOffset(offset),
+			EditAction::SetOffset(prev_val),
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history.push(
+			edit,
+			match offset {
+				Some(offset) => format!("set offset to {offset:+}ms"),
+				None => "remove offset".to_owned(),
+			},
+		);
+		self.changed = true;
+
+		Ok(())
+	}
+
+	pub fn set_voice(&mut self, idx: u16, voice: Option<Voice>) -> eyre::Result<()> {
+		let prev_val = self
+			.song
+			.lyrics
+			.lines()
+			.get(idx as usize)
+			.ok_or_eyre("Line index out of range")?
+			.voice();
+		if prev_val == voice {
+			return Ok(());
+		}
+		let edit = Edit::new(
+			EditAction::SetVoice { idx, voice },
+			EditAction::SetVoice {
+				idx,
+				voice: prev_val,
+			},
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history.push(
+			edit,
+			match voice {
+				Some(voice) => format!("set voice of line {} to {voice}", idx + 1),
+				None => format!("remove voice from line {}", idx + 1),
+			},
+		);
 		self.changed = true;
 
 		Ok(())
 	}
+
+	/// Moves the line to the next voice, following the F/M/D convention if the lyrics already use it
+	pub fn cycle_voice(&mut self, idx: u16) -> eyre::Result<()> {
+		let lyrics = &self.song.lyrics;
+		let voice = lyrics
+			.lines()
+			.get(idx as usize)
+			.ok_or_eyre("Line index out of range")?
+			.voice();
+		self.set_voice(idx, Voice::cycle(voice, lyrics.has_gendered_voices()))
+	}
+
+	/// Inserts an empty line above or below line `idx` and returns its index
+	pub fn open_line(&mut self, idx: u16, below: bool) -> eyre::Result<u16> {
+		if self.song.lyrics.line_count() <= idx {
+			return Err(eyre::eyre!("Line index out of range"));
+		}
+		// the new line goes right after the line above it, or after the header tags
+		let (new_idx, tags_before) = if below {
+			(idx + 1, 0)
+		} else {
+			(idx, self.song.lyrics.tags_before_line(idx as usize))
+		};
+		let edit = Edit::new(
+			EditAction::InsertLine {
+				idx: new_idx,
+				line: Box::default(),
+				tags_before,
+			},
+			EditAction::RemoveLine { idx: new_idx },
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history
+			.push(edit, format!("insert line {}", new_idx + 1));
+		self.changed = true;
+
+		Ok(new_idx)
+	}
+
+	pub fn delete_line(&mut self, idx: u16) -> eyre::Result<()> {
+		let line = self
+			.song
+			.lyrics
+			.lines()
+			.get(idx as usize)
+			.ok_or_eyre("Line index out of range")?
+			.clone();
+		// the last line is replaced with an empty one, which a removed line can't be inserted before
+		if self.song.lyrics.line_count() == 1 {
+			return self.transform_lyrics("delete line 1", |lyrics| {
+				lyrics.remove_line(0);
+			});
+		}
+		let edit = Edit::new(
+			EditAction::RemoveLine { idx },
+			EditAction::InsertLine {
+				idx,
+				line: Box::new(line),
+				tags_before: self.song.lyrics.tags_before_line(idx as usize),
+			},
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history.push(edit, format!("delete line {}", idx + 1));
+		self.changed = true;
+
+		Ok(())
+	}
+
+	/// Splits line `idx` at byte index `at`, dropping the whitespace around it. The new line gets
+	/// the same voice, and a timestamp as chosen by `timestamp`.
+	pub fn split_line(
+		&mut self,
+		idx: u16,
+		at: usize,
+		timestamp: SplitTimestamp,
+		playback_time: Option<Duration>,
+	) -> eyre::Result<()> {
+		let line = self
+			.song
+			.lyrics
+			.lines()
+			.get(idx as usize)
+			.ok_or_eyre("Line index out of range")?;
+		let text = line.text();
+		let at = at.min(text.len());
+		let start = text[..at].trim_end().len();
+		let end = text.len() - text[at..].trim_start().len();
+
+		let timestamp = match timestamp {
+			SplitTimestamp::Word => line
+				.word_timestamps()
+				.iter()
+				.find(|(word, _)| *word == end)
+				.map(|(_, timestamp)| timestamp.time()),
+			SplitTimestamp::Playback => Some(playback_time.ok_or_eyre("No audio playing")?),
+			SplitTimestamp::None => None,
+		};
+		let edit = Edit::new(
+			EditAction::SplitLine {
+				idx,
+				at: start,
+				skip: end - start,
+				next: Box::new(
+					LyricLine::new(timestamp.map(Into::into), String::new())
+						.with_voice(line.voice()),
+				),
+				tags_before: 0,
+			},
+			EditAction::JoinLines {
+				idx,
+				separator: text[start..end].to_owned(),
+			},
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history.push(edit, format!("split line {}", idx + 1));
+		self.changed = true;
+
+		Ok(())
+	}
+
+	/// Appends the next line to line `idx`, separated by a space, and returns the byte index at
+	/// which it starts
+	pub fn join_lines(&mut self, idx: u16) -> eyre::Result<usize> {
+		let lines = self.song.lyrics.lines();
+		let (Some(line), Some(next)) = (lines.get(idx as usize), lines.get(idx as usize + 1))
+		else {
+			return Err(eyre::eyre!("No line to join with"));
+		};
+		let separator = if line.text().ends_with(char::is_whitespace)
+			|| next.text().starts_with(char::is_whitespace)
+			|| line.text().is_empty()
+			|| next.text().is_empty()
+		{
+			""
+		} else {
+			" "
+		};
+		let mut next_template = next.clone();
+		next_template.set_text(String::new(), Vec::new());
+		let at = line.text().len();
+		let edit = Edit::new(
+			EditAction::JoinLines {
+				idx,
+				separator: separator.to_owned(),
+			},
+			EditAction::SplitLine {
+				idx,
+				at,
+				skip: separator.len(),
+				next: Box::new(next_template),
+				tags_before: self.song.lyrics.tags_before_line(idx as usize + 1),
+			},
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history
+			.push(edit, format!("join lines {} and {}", idx + 1, idx + 2));
+		self.changed = true;
+
+		Ok(at + separator.len())
+	}
+
+	/// Moves line `idx` up or down by `amount` and returns where it ends up
+	pub fn move_line(&mut self, idx: u16, amount: i16) -> eyre::Result<u16> {
+		let last = self.song.lyrics.line_count() - 1;
+		if last < idx {
+			return Err(eyre::eyre!("Line index out of range"));
+		}
+		let to = idx.saturating_add_signed(amount).min(last);
+		if to == idx {
+			return Ok(idx);
+		}
+		let edit = Edit::new(
+			EditAction::MoveLine { from: idx, to },
+			EditAction::MoveLine { from: to, to: idx },
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history
+			.push(edit, format!("move line {} to {}", idx + 1, to + 1));
+		self.changed = true;
+
+		Ok(to)
+	}
+
+	/// Moves every timestamp of the lines in `range` by `millis`, stopping at zero
+	pub fn shift_timestamps(
+		&mut self,
+		range: RangeInclusive<u16>,
+		millis: i64,
+	) -> eyre::Result<()> {
+		let description = format!(
+			"shift {} by {:+}s",
+			describe_lines(&range),
+			millis as f64 / 1000.
+		);
+		self.transform_lines(
+			range,
+			description,
+			|lines| {
+				for line in lines {
+					line.map_timestamps(|time| Lyrics::offset_time(time, millis));
+				}
+			},
+			false,
+		)
+	}
+
+	/// Remaps every timestamp of the lines in `range` with `stretch`
+	pub fn stretch_timestamps(
+		&mut self,
+		range: RangeInclusive<u16>,
+		stretch: TimeStretch,
+	) -> eyre::Result<()> {
+		let description = format!("time-stretch {}", describe_lines(&range));
+		self.transform_lines(
+			range,
+			description,
+			|lines| {
+				for line in lines {
+					line.map_timestamps(|time| stretch.apply(time));
+				}
+			},
+			false,
+		)
+	}
+
+	/// Fills in the timestamps of unsynced lines between synced lines in `range`, see
+	/// [`Interpolation::fill`]
+	pub fn interpolate_timestamps(
+		&mut self,
+		range: RangeInclusive<u16>,
+		interpolation: Interpolation,
+		precision: TimestampPrecision,
+	) -> eyre::Result<()> {
+		let description = format!("fill in timestamps of {}", describe_lines(&range));
+		self.transform_lines(
+			range,
+			description,
+			|lines| interpolation.fill(lines, precision),
+			false,
+		)
+	}
+
+	/// Removes the line, repeat and word timestamps of the lines in `range`
+	pub fn clear_timestamps(&mut self, range: RangeInclusive<u16>) -> eyre::Result<()> {
+		let description = format!("unsync {}", describe_lines(&range));
+		self.transform_lines(
+			range,
+			description,
+			|lines| lines.iter_mut().for_each(LyricLine::clear_timestamps),
+			false,
+		)
+	}
+
+	/// Gives the lines from `idx` on the timestamps of `source`, moved so that the first one
+	/// lands on the timestamp line `idx` already has. Word timestamps are only taken over by lines
+	/// with the same text, other lines keep their own moved along with the line timestamp, or lose
+	/// them if the line wasn't synced before or isn't after.
+	pub fn paste_timestamps(&mut self, idx: u16, source: &[LyricLine]) -> eyre::Result<()> {
+		let line_count = self.song.lyrics.line_count();
+		if line_count <= idx {
+			return Err(eyre::eyre!("Line index out of range"));
+		}
+		if source.is_empty() {
+			return Err(eyre::eyre!("No timestamps copied"));
+		}
+		let shift = match (self.song.lyrics.time_at_line(idx), source[0].timestamp()) {
+			(Some(target), Some(copied)) => {
+				target.time().as_millis() as i64 - copied.time().as_millis() as i64
+			}
+			_ => 0,
+		};
+		let end = (idx as usize + source.len()).min(line_count as usize) as u16 - 1;
+
+		self.transform_lines(
+			idx..=end,
+			format!("paste timestamps onto {}", describe_lines(&(idx..=end))),
+			|lines| {
+				for (line, source) in lines.iter_mut().zip(source) {
+					let mut copied = source.clone();
+					copied.map_timestamps(|time| Lyrics::offset_time(time, shift));
+					if line.text() == copied.text() {
+						line.set_text(copied.text().to_owned(), copied.word_timestamps().to_vec());
+					} else {
+						let moved = match (line.timestamp(), copied.timestamp()) {
+							(Some(old), Some(new)) => {
+								Some(new.time().as_millis() as i64 - old.time().as_millis() as i64)
+							}
+							_ => None,
+						};
+						let word_timestamps = match moved {
+							Some(moved) => line
+								.word_timestamps()
+								.iter()
+								.map(|(idx, timestamp)| {
+									let time = Lyrics::offset_time(timestamp.time(), moved);
+									(*idx, timestamp.with_time(time))
+								})
+								.collect(),
+							None => Vec::new(),
+						};
+						line.set_text(line.text().to_owned(), word_timestamps);
+					}
+					line.set_timestamp(copied.timestamp().map(Timestamp::time));
+					line.set_repeat_timestamps(copied.repeat_timestamps().to_vec());
+				}
+			},
+			false,
+		)
+	}
+
+	/// Sets the timestamp of line `idx` while the lines in `range` are synced again one after
+	/// another. With `merge`, the change is undone together with the last edit if that synced an
+	/// earlier line of the same range.
+	pub fn resync_line(
+		&mut self,
+		range: RangeInclusive<u16>,
+		idx: u16,
+		time: Duration,
+		merge: bool,
+	) -> eyre::Result<()> {
+		if !range.contains(&idx) {
+			return Err(eyre::eyre!("Line is not being synced"));
+		}
+		let start = *range.start();
+		let len = (range.end() - start + 1) as usize;
+		let merge = merge
+			&& self.history.last().is_some_and(|edit| {
+				matches!(edit.forwards_action(), EditAction::SetLines { idx, lines }
+					if *idx == start && lines.len() == len)
+			});
+
+		let description = format!("re-sync {}", describe_lines(&range));
+		self.transform_lines(
+			range,
+			description,
+			|lines| lines[(idx - start) as usize].set_timestamp(Some(time)),
+			merge,
+		)
+	}
+
+	/// Replaces the bytes in `range` of the text of line `idx`. With `merge`, the change is undone
+	/// together with the last edit, as long as it changed the text of the same line.
+	pub fn replace_text(
+		&mut self,
+		idx: u16,
+		range: Range<usize>,
+		text: &str,
+		merge: bool,
+	) -> eyre::Result<()> {
+		let line = self
+			.song
+			.lyrics
+			.lines()
+			.get(idx as usize)
+			.ok_or_eyre("Line index out of range")?;
+		if line.text().get(range.clone()).is_none() {
+			return Err(eyre::eyre!("Text range out of bounds"));
+		}
+		let mut new_line = line.clone();
+		new_line.replace_text(range, text);
+		let merge = merge
+			&& self.history.last().is_some_and(|edit| {
+				matches!(edit.forwards_action(), EditAction::SetText { idx: last, .. }
+					if *last == idx)
+			});
+
+		let edit = Edit::new(
+			EditAction::SetText {
+				idx,
+				text: new_line.text().to_owned(),
+				word_timestamps: new_line.word_timestamps().to_vec(),
+			},
+			EditAction::SetText {
+				idx,
+				text: line.text().to_owned(),
+				word_timestamps: line.word_timestamps().to_vec(),
+			},
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		let description = format!("edit text of line {}", idx + 1);
+		if merge {
+			self.history.push_merged(edit, description);
+		} else {
+			self.history.push(edit, description);
+		}
+		self.changed = true;
+
+		Ok(())
+	}
+
+	pub fn fix_safe_problems(&mut self, track: &TrackInfo) -> eyre::Result<()> {
+		self.transform_lyrics("fix safe problems", |lyrics| lint::fix_safe(lyrics, track))
+	}
+
+	pub fn bake_offset(&mut self) -> eyre::Result<()> {
+		self.transform_lyrics("apply offset to timestamps", Lyrics::bake_offset)
+	}
+
+	pub fn expand_repeats(&mut self) -> eyre::Result<()> {
+		self.transform_lyrics("expand repeated lines", Lyrics::expand_repeats)
+	}
+
+	pub fn compress_repeats(&mut self) -> eyre::Result<()> {
+		self.transform_lyrics("compress repeated lines", Lyrics::compress_repeats)
+	}
+
+	/// Applies a change to the lines in `range` as a single undoable edit. With `merge`, the
+	/// change is undone together with the last edit, which must have changed the same lines.
+	fn transform_lines(
+		&mut self,
+		range: RangeInclusive<u16>,
+		description: String,
+		transform: impl FnOnce(&mut [LyricLine]),
+		merge: bool,
+	) -> eyre::Result<()> {
+		let idx = *range.start();
+		let lines = self
+			.song
+			.lyrics
+			.lines()
+			.get(idx as usize..=*range.end() as usize)
+			.ok_or_eyre("Line index out of range")?;
+		let mut new_lines = lines.to_vec();
+		transform(&mut new_lines);
+		if new_lines == lines {
+			return Ok(());
+		}
+
+		let edit = Edit::new(
+			EditAction::SetLines {
+				idx,
+				lines: new_lines,
+			},
+			EditAction::SetLines {
+				idx,
+				lines: lines.to_vec(),
+			},
+		);
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		if merge {
+			self.history.push_merged(edit, description);
+		} else {
+			self.history.push(edit, description);
+		}
+		self.changed = true;
+
+		Ok(())
+	}
+
+	/// Applies a change to the whole of the lyrics as a single undoable edit
+	fn transform_lyrics(
+		&mut self,
+		description: &str,
+		transform: impl FnOnce(&mut Lyrics),
+	) -> eyre::Result<()> {
+		let mut lyrics = self.song.lyrics.clone();
+		transform(&mut lyrics);
+
+		let Some(edit) = Edit::between(&self.song.lyrics, &lyrics) else {
+			return Ok(());
+		};
+		edit.execute_forwards(&mut self.song.lyrics, &mut self.time_index)?;
+		self.history.push(edit, description.to_owned());
+		self.changed = true;
+
+		Ok(())
+	}
+}
+
+/// "line 3" or "lines 3-8", counting from 1 like the problems list
+fn describe_lines(range: &RangeInclusive<u16>) -> String {
+	if range.start() == range.end() {
+		format!("line {}", range.start() + 1)
+	} else {
+		format!("lines {}-{}", range.start() + 1, range.end() + 1)
+	}
+}
+
+/// "set `what` to 01:02.33", or "unsync `what`"
+fn describe_timestamp(what: &str, timestamp: Option<Duration>) -> String {
+	match timestamp {
+		Some(time) => format!("set {what} to {}", Timestamp::from(time).text()),
+		None => format!("unsync {what}"),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("lrxed-{}-{name}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	fn open(dir: &Path, settings: &Settings) -> SongState {
+		let mut state = SongState::default();
+		let song = Song::from_file(&dir.join("song.mp3")).unwrap();
+		state
+			.load_from_song(song, Some(dir.join("history")), settings)
+			.unwrap();
+		state
+	}
+
+	fn texts(state: &SongState) -> Vec<String> {
+		let mut bytes = Vec::new();
+		state
+			.song
+			.lyrics
+			.write_to(&mut bytes, TimestampPrecision::Keep)
+			.unwrap();
+		String::from_utf8(bytes)
+			.unwrap()
+			.lines()
+			.map(str::to_owned)
+			.collect()
+	}
+
+	#[test]
+	fn history_is_kept_when_saving_compressed_lines() {
+		let dir = temp_dir("compressed-history");
+		fs::write(dir.join("song.mp3"), "").unwrap();
+		fs::write(
+			dir.join("song.lrc"),
+			"[00:01.00]la\n[00:02.00]da\n[00:03.00]la\n",
+		)
+		.unwrap();
+		let settings = Settings {
+			compress_repeated_lines_on_save: true,
+			..Settings::default()
+		};
+
+		let mut state = open(&dir, &settings);
+		state
+			.set_timestamp(Position::new(0, 1), Some(Duration::from_millis(2500)))
+			.unwrap();
+		state
+			.write_to_file(&settings, &TrackInfo::default())
+			.unwrap();
+
+		let mut state = open(&dir, &settings);
+		assert_eq!(texts(&state), ["[00:01.00][00:03.00] la", "[00:02.50] da"]);
+		state.undo().unwrap();
+		assert_eq!(
+			texts(&state),
+			["[00:01.00]la", "[00:02.50] da", "[00:03.00]la"]
+		);
+		state.undo().unwrap();
+		assert_eq!(
+			texts(&state),
+			["[00:01.00]la", "[00:02.00] da", "[00:03.00]la"]
+		);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn export_refuses_to_overwrite_the_lyrics_file() {
+		let dir = temp_dir("export");
+		fs::write(
+			dir.join("song.srt"),
+			"1\n00:00:01,000 --> 00:00:02,000\nline\n",
+		)
+		.unwrap();
+		let mut state = SongState::default();
+		state.song.lrc_file = dir.join("song.srt");
+		state.song.lyrics = LyricsFormat::Srt
+			.read(&fs::read(dir.join("song.srt")).unwrap()[..])
+			.unwrap();
+		let settings = Settings::default();
+
+		assert!(
+			state
+				.export_lyrics(LyricsFormat::Srt, &settings, None)
+				.is_err()
+		);
+		state
+			.export_lyrics(LyricsFormat::Vtt, &settings, None)
+			.unwrap();
+		assert!(
+			fs::read_to_string(dir.join("song.vtt"))
+				.unwrap()
+				.starts_with("WEBVTT")
+		);
+		fs::remove_dir_all(dir).unwrap();
+	}
 }
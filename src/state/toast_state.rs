@@ -3,16 +3,24 @@ use std::{
 	time::{Duration, Instant},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+	Error,
+	Warning,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Toast {
 	pub text: String,
+	pub kind: ToastKind,
 	spawn_time: Instant,
 }
 
 impl Toast {
-	fn new(text: String) -> Self {
+	fn new(text: String, kind: ToastKind) -> Self {
 		Self {
 			text,
+			kind,
 			spawn_time: Instant::now(),
 		}
 	}
@@ -29,7 +37,15 @@ pub struct ToastState {
 
 impl ToastState {
 	pub fn push(&mut self, toast_text: String) {
-		self.toast_queue.push_front(Toast::new(toast_text));
+		self.push_kind(toast_text, ToastKind::Error);
+	}
+
+	pub fn push_warning(&mut self, toast_text: String) {
+		self.push_kind(toast_text, ToastKind::Warning);
+	}
+
+	pub fn push_kind(&mut self, toast_text: String, kind: ToastKind) {
+		self.toast_queue.push_front(Toast::new(toast_text, kind));
 	}
 
 	pub fn cull(&mut self, timeout: Duration) {
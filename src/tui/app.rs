@@ -14,7 +14,8 @@ use super::{
 	Modal, View,
 	input_handler::InputHandler,
 	views::{
-		ConfirmBackModal, ConfirmQuitModal, EditorView, FileTreeView, KeysModal, ToastsOverlay,
+		ConfirmBackModal, ConfirmOverwriteModal, ConfirmQuitModal, EditorView, FileTreeView,
+		HistoryModal, KeysModal, ProblemsModal, ToastsOverlay,
 	},
 };
 
@@ -75,8 +76,13 @@ impl InputHandler for App {
 		let consumed = if let Some(modal) = state.active_modal {
 			match modal {
 				Modal::ConfirmQuit => ConfirmQuitModal.handle_input(key_chord, state),
+				Modal::ConfirmOverwrite { format } => {
+					ConfirmOverwriteModal { format }.handle_input(key_chord, state)
+				}
 				Modal::GoBack => ConfirmBackModal.handle_input(key_chord, state),
+				Modal::History => HistoryModal.handle_input(key_chord, state),
 				Modal::Keys => KeysModal.handle_input(key_chord, state),
+				Modal::Problems => ProblemsModal.handle_input(key_chord, state),
 			}?
 		} else {
 			match state.active_view {
@@ -125,8 +131,13 @@ impl StatefulWidget for App {
 		if let Some(modal) = state.active_modal {
 			match modal {
 				Modal::ConfirmQuit => ConfirmQuitModal.render(area, buf, state),
+				Modal::ConfirmOverwrite { format } => {
+					ConfirmOverwriteModal { format }.render(area, buf, state)
+				}
 				Modal::GoBack => ConfirmBackModal.render(area, buf, state),
+				Modal::History => HistoryModal.render(area, buf, state),
 				Modal::Keys => KeysModal.render(area, buf, state),
+				Modal::Problems => ProblemsModal.render(area, buf, state),
 			};
 		}
 	}
@@ -9,6 +9,8 @@ pub struct Cursor {
 	render_origin: Option<Position>,
 	scroll: Position,
 	screen_size: Position,
+	/// whether the cursor may be placed after the last character, for typing at the end of a line
+	past_end: bool,
 }
 
 impl Cursor {
@@ -37,11 +39,18 @@ impl Cursor {
 					)
 				});
 		self.editor_pos.y = min(len_y - 1, self.target_pos.y);
-		self.editor_pos.x = min(self.target_pos.x, max(len_x, 1) - 1);
+		self.editor_pos.x = if self.past_end {
+			min(self.target_pos.x, len_x)
+		} else {
+			min(self.target_pos.x, max(len_x, 1) - 1)
+		};
 		self
 	}
 
-	pub fn update_scroll(&mut self, text_size: Position, scrolloff: u16) -> &mut Self {
+	pub fn update_scroll(&mut self, mut text_size: Position, scrolloff: u16) -> &mut Self {
+		if self.past_end {
+			text_size.x += 1;
+		}
 		self.update_vertical_scroll(
 			text_size.y,
 			self.screen_size.y,
@@ -75,11 +84,17 @@ impl Cursor {
 		self.scroll.x = scroll_end - screen_width;
 	}
 
+	/// Sets where the text of the line the cursor is on starts on screen
 	pub fn set_render_origin(&mut self, origin: Option<Position>) -> &mut Self {
 		self.render_origin = origin;
 		self
 	}
 
+	pub fn set_past_end(&mut self, past_end: bool) -> &mut Self {
+		self.past_end = past_end;
+		self
+	}
+
 	pub fn set_screen_size(&mut self, size: Position) -> &mut Self {
 		self.screen_size = size;
 		self
@@ -93,7 +108,7 @@ impl Cursor {
 		self.render_origin.map(|render_origin| {
 			Position::new(
 				self.editor_pos.x + render_origin.x - self.scroll.x,
-				self.editor_pos.y + render_origin.y - self.scroll.y,
+				render_origin.y,
 			)
 		})
 	}
@@ -6,4 +6,4 @@ mod widgets;
 
 pub use app::App;
 pub use cursor::Cursor;
-pub use views::{Modal, View};
+pub use views::{EditorMode, Modal, View};
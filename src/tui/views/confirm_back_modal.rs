@@ -10,9 +10,7 @@ impl ConfirmModal for ConfirmBackModal {
 	const PROMPT: &str = "Save changes before returning to file browser?";
 
 	fn exec_yes(self, state: &mut AppState) -> eyre::Result<()> {
-		state
-			.song
-			.write_to_file(state.config.settings.replace_txt_file_on_save)?;
+		state.save_song()?;
 
 		state
 			.file_browser
@@ -0,0 +1,34 @@
+use crate::{lyrics::LyricsFormat, state::AppState, tui::views::confirm_modal::ConfirmModal};
+use color_eyre::eyre;
+use ratatui::widgets::StatefulWidget;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfirmOverwriteModal {
+	pub format: LyricsFormat,
+}
+
+impl ConfirmModal for ConfirmOverwriteModal {
+	const TITLE: &str = "Overwrite?";
+	const PROMPT: &str = "The file to export to exists already. Overwrite it?";
+
+	fn exec_yes(self, state: &mut AppState) -> eyre::Result<()> {
+		state.export_lyrics(self.format)
+	}
+
+	fn exec_no(self, _state: &mut AppState) -> eyre::Result<()> {
+		Ok(())
+	}
+}
+
+impl StatefulWidget for ConfirmOverwriteModal {
+	type State = AppState;
+
+	fn render(
+		self,
+		area: ratatui::prelude::Rect,
+		buf: &mut ratatui::prelude::Buffer,
+		state: &mut Self::State,
+	) {
+		ConfirmModal::render(&self, area, buf, state);
+	}
+}
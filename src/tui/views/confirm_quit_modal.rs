@@ -10,9 +10,7 @@ impl ConfirmModal for ConfirmQuitModal {
 	const PROMPT: &str = "Save changes before quitting?";
 
 	fn exec_yes(self, state: &mut AppState) -> eyre::Result<()> {
-		state
-			.song
-			.write_to_file(state.config.settings.replace_txt_file_on_save)?;
+		state.save_song()?;
 
 		state.should_quit = true;
 
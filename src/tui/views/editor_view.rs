@@ -1,10 +1,11 @@
 use std::{
 	cmp::{max, min},
+	ops::{Range, RangeInclusive},
 	time::Duration,
-	u16,
 };
 
 use color_eyre::eyre::{self, OptionExt};
+use crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::{
 	layout::{Constraint, Layout, Position},
 	widgets::StatefulWidget,
@@ -15,10 +16,15 @@ use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use crate::{
 	audio::AudioPlayer,
 	config::{Action, Context, KeyChord},
+	lyrics::{
+		editing::{StretchAnchor, TimeStretch},
+		lint,
+	},
 	state::AppState,
 	tui::{
-		Modal, View,
+		EditorMode, Modal, View,
 		input_handler::InputHandler,
+		views::HistoryModal,
 		widgets::{LyricsWidget, PlaybackWidget},
 	},
 };
@@ -31,83 +37,266 @@ impl EditorView {
 		state.active_view = View::FileTree;
 		state.audio = Default::default();
 		state.song = Default::default();
+		state.editor_mode = EditorMode::Normal;
+		state.stretch_anchors.clear();
+		state.cursor.set_past_end(false);
 		state.should_go_back = false;
 	}
-}
 
-impl InputHandler for EditorView {
-	type State = AppState;
+	fn enter_insert_mode(self, state: &mut AppState) {
+		state.editor_mode = EditorMode::Insert { edited_line: None };
+		state.cursor.set_past_end(true);
+	}
 
-	fn handle_input(self, key_chord: KeyChord, state: &mut AppState) -> eyre::Result<bool> {
-		if let Some(action) = state
-			.config
-			.keys
-			.get_action(key_chord, Context::Editor)
-			.or(state.config.keys.get_action(key_chord, Context::Global))
-		{
-			match action {
-				Action::Save => {
-					state
-						.song
-						.write_to_file(state.config.settings.replace_txt_file_on_save)?;
+	fn leave_insert_mode(self, state: &mut AppState) {
+		state.editor_mode = EditorMode::Normal;
+		// like in vim, the cursor moves back onto the last typed character
+		let pos = state.cursor.pos();
+		let line = &state.song.song.lyrics.lines()[pos.y as usize];
+		let x = line.text()[..line.byte_index_at(pos.x)]
+			.char_indices()
+			.next_back()
+			.map_or(0, |(idx, _)| line.column_at(idx));
+		state.cursor.set_past_end(false);
+		state.jump_cursor(Position::new(x, pos.y));
+	}
 
-					state
-						.file_browser
-						.update_selected_song(state.song.song.clone());
-				}
-				Action::MoveCursorY { amount } => {
-					state
-						.cursor
-						.set_y(max(state.cursor.pos().y as i16 + amount, 0) as u16)
-						.update_pos(state.song.song.lyrics.line_widths())
-						.update_scroll(
-							Position::new(
-								state
-									.song
-									.song
-									.lyrics
-									.line_widths()
-									.max()
-									.unwrap_or_default(),
-								state.song.song.lyrics.line_count(),
-							),
-							state.config.settings.scrolloff,
-						);
-					state.cursor.set_y(state.cursor.pos().y);
+	/// Keys bound in the insert context act as usual, other characters are typed at the cursor
+	fn handle_insert_input(self, key_chord: KeyChord, state: &mut AppState) -> eyre::Result<bool> {
+		match state.config.keys.get_action(key_chord, Context::Insert) {
+			Some(Action::Cancel | Action::Confirm) => self.leave_insert_mode(state),
+			Some(Action::DeleteCharacter { amount }) => delete_at_cursor(state, amount)?,
+			Some(action) => {
+				self.handle_action(action, state)?;
+			}
+			None => {
+				if let KeyCode::Char(c) = key_chord.key
+					&& !key_chord
+						.mods
+						.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SUPER)
+				{
+					let pos = state.cursor.pos();
+					let idx = state.song.song.lyrics.lines()[pos.y as usize].byte_index_at(pos.x);
+					replace_at_cursor(state, idx..idx, c.encode_utf8(&mut [0; 4]))?;
 				}
-				Action::MoveCursorX { amount } => {
-					let line = state.song.song.lyrics.lines()[state.cursor.pos().y as usize].text();
-
-					let x = if amount >= 0 {
-						line.chars()
-							.scan(0, |head, c| {
-								let char_pos = *head as u16;
-								let char_width = c.width().unwrap_or_default() as u16;
-								*head += char_width;
-								Some((char_pos, char_width))
-							})
-							.skip_while(|(char_pos, char_width)| {
-								char_pos + char_width <= state.cursor.pos().x
-							})
-							.map(|(char_pos, _)| char_pos)
-							.nth(amount as usize)
-							.unwrap_or(u16::MAX)
-					} else {
-						line.chars()
-							.rev()
-							.scan(line.width() as u16, |head, c| {
-								let char_width = c.width().unwrap_or_default() as u16;
-								*head -= char_width;
-								Some(*head)
-							})
-							.skip_while(|&char_pos| char_pos > state.cursor.pos().x)
-							.nth(amount.abs() as usize)
-							.unwrap_or_default()
-					};
+			}
+		}
+		// unbound keys must not fall through to the global keys, which are letters too
+		Ok(true)
+	}
+
+	/// Actions which act on all selected lines in visual mode or continue re-syncing them, any
+	/// other action is handled as usual
+	fn handle_selection_action(
+		self,
+		action: Action,
+		selection: RangeInclusive<u16>,
+		state: &mut AppState,
+	) -> eyre::Result<bool> {
+		// lines may have been deleted since the selection was started
+		let last = state.song.song.lyrics.line_count() - 1;
+		let (start, end) = (min(*selection.start(), last), min(*selection.end(), last));
+		let selection = start..=end;
+		let y = state.cursor.pos().y;
 
+		match (action, state.editor_mode) {
+			(Action::Cancel | Action::EnterVisualMode, _) => {
+				state.editor_mode = EditorMode::Normal;
+			}
+			(Action::AdjustTimestamp { centis }, EditorMode::Visual { .. }) => {
+				state.song.shift_timestamps(selection, centis as i64 * 10)?;
+			}
+			(Action::ClearTimestamps, EditorMode::Visual { .. }) => {
+				state.song.clear_timestamps(selection)?;
+				state.editor_mode = EditorMode::Normal;
+			}
+			(Action::CopyTimestamps, EditorMode::Visual { .. }) => {
+				state.copied_lines =
+					state.song.song.lyrics.lines()[start as usize..=end as usize].to_vec();
+				state.editor_mode = EditorMode::Normal;
+			}
+			(Action::PasteTimestamps, EditorMode::Visual { .. }) => {
+				let len = state.copied_lines.len().min((end - start + 1) as usize);
+				state
+					.song
+					.paste_timestamps(start, &state.copied_lines[..len])?;
+				state.editor_mode = EditorMode::Normal;
+			}
+			(Action::StretchTimestamps, EditorMode::Visual { .. }) => {
+				stretch_timestamps(state, selection)?;
+				state.editor_mode = EditorMode::Normal;
+			}
+			(Action::InterpolateTimestamps { interpolation }, EditorMode::Visual { .. }) => {
+				state.song.interpolate_timestamps(
+					selection,
+					interpolation,
+					state.config.settings.timestamp_precision,
+				)?;
+				state.editor_mode = EditorMode::Normal;
+			}
+			(Action::SyncTimestamp, EditorMode::Visual { .. }) => {
+				state.editor_mode = EditorMode::Resync {
+					start,
+					end,
+					synced: false,
+				};
+				state.jump_cursor(Position::new(0, start));
+			}
+			(Action::SyncTimestamp, EditorMode::Resync { synced, .. })
+				if selection.contains(&y) =>
+			{
+				let time = get_sync_time(state)?;
+				state.song.resync_line(selection, y, time, synced)?;
+				state.editor_mode = if y < end {
+					EditorMode::Resync {
+						start,
+						end,
+						synced: true,
+					}
+				} else {
+					EditorMode::Normal
+				};
+				state.jump_cursor(Position::new(state.cursor.pos().x, y + 1));
+			}
+			(Action::SyncTimestamp, EditorMode::Resync { .. }) => {
+				state.editor_mode = EditorMode::Normal;
+				return Ok(false);
+			}
+			_ => return Ok(false),
+		}
+		Ok(true)
+	}
+
+	fn handle_action(self, action: Action, state: &mut AppState) -> eyre::Result<bool> {
+		match action {
+			Action::Save => {
+				state.save_song()?;
+
+				state
+					.file_browser
+					.update_selected_song(state.song.song.clone());
+			}
+			Action::MoveCursorY { amount } => {
+				state
+					.cursor
+					.set_y(max(state.cursor.pos().y as i16 + amount, 0) as u16)
+					.update_pos(state.song.song.lyrics.line_widths())
+					.update_scroll(
+						Position::new(
+							state
+								.song
+								.song
+								.lyrics
+								.line_widths()
+								.max()
+								.unwrap_or_default(),
+							state.song.song.lyrics.line_count(),
+						),
+						state.config.settings.scrolloff,
+					);
+				state.cursor.set_y(state.cursor.pos().y);
+			}
+			Action::MoveCursorX { amount } => {
+				let line = state.song.song.lyrics.lines()[state.cursor.pos().y as usize].text();
+
+				let x = if amount >= 0 {
+					line.chars()
+						.scan(0u16, |head, c| {
+							let char_pos = *head;
+							let char_width = c.width().unwrap_or_default() as u16;
+							*head += char_width;
+							Some((char_pos, char_width))
+						})
+						.skip_while(|(char_pos, char_width)| {
+							char_pos + char_width <= state.cursor.pos().x
+						})
+						.map(|(char_pos, _)| char_pos)
+						.nth(amount as usize)
+						.unwrap_or(u16::MAX)
+				} else {
+					line.chars()
+						.rev()
+						.scan(line.width() as u16, |head, c| {
+							let char_width = c.width().unwrap_or_default() as u16;
+							*head -= char_width;
+							Some(*head)
+						})
+						.skip_while(|&char_pos| char_pos > state.cursor.pos().x)
+						.nth(amount.unsigned_abs() as usize)
+						.unwrap_or_default()
+				};
+
+				state
+					.cursor
+					.set_x(x)
+					.update_pos(state.song.song.lyrics.line_widths())
+					.update_scroll(
+						Position::new(
+							state
+								.song
+								.song
+								.lyrics
+								.line_widths()
+								.max()
+								.unwrap_or_default(),
+							state.song.song.lyrics.line_count(),
+						),
+						state.config.settings.scrolloff,
+					);
+				state.cursor.set_x(state.cursor.pos().x);
+			}
+			Action::SetCursorY { y } => {
+				state
+					.cursor
+					.set_y(y)
+					.update_pos(state.song.song.lyrics.line_widths())
+					.update_scroll(
+						Position::new(
+							state
+								.song
+								.song
+								.lyrics
+								.line_widths()
+								.max()
+								.unwrap_or_default(),
+							state.song.song.lyrics.line_count(),
+						),
+						state.config.settings.scrolloff,
+					);
+			}
+			Action::SetCursorX { x } => {
+				state
+					.cursor
+					.set_x(x)
+					.update_pos(state.song.song.lyrics.line_widths())
+					.update_scroll(
+						Position::new(
+							state
+								.song
+								.song
+								.lyrics
+								.line_widths()
+								.max()
+								.unwrap_or_default(),
+							state.song.song.lyrics.line_count(),
+						),
+						state.config.settings.scrolloff,
+					);
+			}
+			Action::CursorToPlaying => {
+				let player = get_player(state)?;
+
+				let time;
+				(time, state.song.time_index_hint) = state
+					.song
+					.time_index
+					.find_seq(player.position(), state.song.time_index_hint);
+
+				if let Some(y) = time.line_num {
 					state
 						.cursor
-						.set_x(max(x, 0) as u16)
+						.set_y(y)
+						.set_x(time.x)
 						.update_pos(state.song.song.lyrics.line_widths())
 						.update_scroll(
 							Position::new(
@@ -122,9 +311,18 @@ impl InputHandler for EditorView {
 							),
 							state.config.settings.scrolloff,
 						);
-					state.cursor.set_x(state.cursor.pos().x);
 				}
-				Action::SetCursorY { y } => {
+			}
+			Action::CursorToPlayingLine => {
+				let player = get_player(state)?;
+
+				let time;
+				(time, state.song.time_index_hint) = state
+					.song
+					.time_index
+					.find_seq(player.position(), state.song.time_index_hint);
+
+				if let Some(y) = time.line_num {
 					state
 						.cursor
 						.set_y(y)
@@ -143,214 +341,357 @@ impl InputHandler for EditorView {
 							state.config.settings.scrolloff,
 						);
 				}
-				Action::SetCursorX { x } => {
-					state
-						.cursor
-						.set_x(x)
-						.update_pos(state.song.song.lyrics.line_widths())
-						.update_scroll(
-							Position::new(
-								state
-									.song
-									.song
-									.lyrics
-									.line_widths()
-									.max()
-									.unwrap_or_default(),
-								state.song.song.lyrics.line_count(),
-							),
-							state.config.settings.scrolloff,
-						);
+			}
+			Action::SeekRelative { progress } => {
+				let pos = state.audio.seek_relative(progress)?;
+				if let Some(time) = pos {
+					(_, state.song.time_index_hint) = state.song.time_index.find_random(time);
 				}
-				Action::CursorToPlaying => {
-					let player = get_player(state)?;
-
-					// TODO: get cursor x position from time and set it
-					let time;
-					(time, state.song.time_index_hint) = state
-						.song
-						.time_index
-						.find_seq(player.position(), state.song.time_index_hint);
+			}
+			Action::SeekBackwards { seconds } => {
+				let player = get_player(state)?;
+				let pos = player.position();
+				player.seek(pos - min(Duration::from_secs_f32(seconds), pos))?;
+			}
+			Action::SeekForwards { seconds } => {
+				let player = get_player(state)?;
+				player.seek(player.position() + Duration::from_secs_f32(seconds))?;
+			}
+			Action::SeekToCursor => {
+				let player = get_player(state)?;
 
-					if let Some(y) = time.line_num {
-						state
-							.cursor
-							.set_y(y)
-							.update_pos(state.song.song.lyrics.line_widths())
-							.update_scroll(
-								Position::new(
-									state
-										.song
-										.song
-										.lyrics
-										.line_widths()
-										.max()
-										.unwrap_or_default(),
-									state.song.song.lyrics.line_count(),
-								),
-								state.config.settings.scrolloff,
-							);
-					}
+				if let Some(timestamp) = state
+					.song
+					.song
+					.lyrics
+					.time_at_cursor(state.cursor.pos().x, state.cursor.pos().y)
+				{
+					let time = state.song.song.lyrics.apply_offset(timestamp.time());
+					player.seek(time + Duration::from_millis(1))?;
+					(_, state.song.time_index_hint) = state.song.time_index.find_random(time);
 				}
-				Action::CursorToPlayingLine => {
-					let player = get_player(state)?;
-
-					let time;
-					(time, state.song.time_index_hint) = state
-						.song
-						.time_index
-						.find_seq(player.position(), state.song.time_index_hint);
+			}
+			Action::SeekToCursorLine => {
+				let player = get_player(state)?;
 
-					if let Some(y) = time.line_num {
-						state
-							.cursor
-							.set_y(y)
-							.update_pos(state.song.song.lyrics.line_widths())
-							.update_scroll(
-								Position::new(
-									state
-										.song
-										.song
-										.lyrics
-										.line_widths()
-										.max()
-										.unwrap_or_default(),
-									state.song.song.lyrics.line_count(),
-								),
-								state.config.settings.scrolloff,
-							);
-					}
+				if let Some(timestamp) = state.song.song.lyrics.time_at_line(state.cursor.pos().y) {
+					let time = state.song.song.lyrics.apply_offset(timestamp.time());
+					player.seek(time + Duration::from_millis(1))?;
+					(_, state.song.time_index_hint) = state.song.time_index.find_random(time);
 				}
-				Action::SeekRelative { progress } => {
-					let pos = state.audio.seek_relative(progress)?;
-					if let Some(time) = pos {
-						(_, state.song.time_index_hint) = state.song.time_index.find_random(time);
-					}
-				}
-				Action::SeekBackwards { seconds } => {
-					let player = get_player(state)?;
-					let pos = player.position();
-					player.seek(pos - min(Duration::from_secs_f32(seconds), pos))?;
-				}
-				Action::SeekForwards { seconds } => {
-					let player = get_player(state)?;
-					player.seek(player.position() + Duration::from_secs_f32(seconds))?;
-				}
-				Action::SeekToCursor => {
-					let player = get_player(state)?;
-
-					if let Some(timestamp) = state
-						.song
-						.song
-						.lyrics
-						.time_at_cursor(state.cursor.pos().x, state.cursor.pos().y)
-					{
-						player.seek(timestamp.time() + Duration::from_millis(1))?;
-						(_, state.song.time_index_hint) =
-							state.song.time_index.find_random(timestamp.time());
-					}
-				}
-				Action::SeekToCursorLine => {
-					let player = get_player(state)?;
-
-					if let Some(timestamp) =
-						state.song.song.lyrics.time_at_line(state.cursor.pos().y)
-					{
-						player.seek(timestamp.time() + Duration::from_millis(1))?;
-						(_, state.song.time_index_hint) =
-							state.song.time_index.find_random(timestamp.time());
-					}
-				}
-				Action::TogglePause => {
-					let player = get_player(state)?;
-					player.set_paused(!player.is_paused());
-				}
-				Action::ChangeVolume { percentage } => {
-					let player = get_player(state)?;
-					let volume = (player.volume() * 100. + 0.5) as i16 + percentage;
-					player.set_volume(min(max(volume, 0), 100) as f32 / 100.);
-				}
-				Action::ChangeSpeed { percentage } => {
-					let player = get_player(state)?;
-					let speed = (player.speed() * 100. + 0.5) as i16 + percentage;
-					player.set_speed(min(max(speed, 50), 200) as f32 / 100.);
-				}
-				Action::ResetSpeed => {
-					let player = get_player(state)?;
-					player.set_speed(1.);
-				}
-				Action::Undo => {
-					state.song.undo()?;
-				}
-				Action::Redo => {
-					state.song.redo()?;
-				}
-				Action::SyncTimestamp => {
-					let player = get_player(state)?;
-					state.song.set_timestamp(
-						state.cursor.pos(),
-						Some(
-							(TimeDelta::from_std(player.position()).unwrap_or_default()
-								+ state.config.settings.sync_offset)
-								.to_std()
-								.unwrap_or_default()
-								.min(player.duration()),
+			}
+			Action::TogglePause => {
+				let player = get_player(state)?;
+				player.set_paused(!player.is_paused());
+			}
+			Action::ChangeVolume { percentage } => {
+				let player = get_player(state)?;
+				let volume = (player.volume() * 100. + 0.5) as i16 + percentage;
+				player.set_volume(volume.clamp(0, 100) as f32 / 100.);
+			}
+			Action::ChangeSpeed { percentage } => {
+				let player = get_player(state)?;
+				let speed = (player.speed() * 100. + 0.5) as i16 + percentage;
+				player.set_speed(speed.clamp(50, 200) as f32 / 100.);
+			}
+			Action::ResetSpeed => {
+				let player = get_player(state)?;
+				player.set_speed(1.);
+			}
+			Action::Undo => {
+				state.song.undo()?;
+			}
+			Action::Redo => {
+				state.song.redo()?;
+			}
+			Action::SyncTimestamp => {
+				let time = get_sync_time(state)?;
+				state.song.set_timestamp(state.cursor.pos(), Some(time))?;
+				state
+					.cursor
+					.set_y(state.cursor.pos().y + 1)
+					.update_pos(state.song.song.lyrics.line_widths())
+					.update_scroll(
+						Position::new(
+							state
+								.song
+								.song
+								.lyrics
+								.line_widths()
+								.max()
+								.unwrap_or_default(),
+							state.song.song.lyrics.line_count(),
 						),
-					)?;
-					state
-						.cursor
-						.set_y(state.cursor.pos().y + 1)
-						.update_pos(state.song.song.lyrics.line_widths())
-						.update_scroll(
-							Position::new(
-								state
-									.song
-									.song
-									.lyrics
-									.line_widths()
-									.max()
-									.unwrap_or_default(),
-								state.song.song.lyrics.line_count(),
-							),
-							state.config.settings.scrolloff,
-						);
-					state.cursor.set_y(state.cursor.pos().y);
+						state.config.settings.scrolloff,
+					);
+				state.cursor.set_y(state.cursor.pos().y);
+			}
+			Action::SyncWordTimestamp => {
+				let time = get_sync_time(state)?;
+				let pos = state.cursor.pos();
+				state.song.set_word_timestamp(pos, Some(time))?;
+
+				let line = &state.song.song.lyrics.lines()[pos.y as usize];
+				let next_word = line
+					.word_at(pos.x)
+					.and_then(|word| line.next_word(word))
+					.map(|word| line.column_at(word));
+				match next_word {
+					Some(x) => state.cursor.set_x(x),
+					None => state.cursor.set_y(pos.y + 1).set_x(0),
 				}
-				Action::AdjustTimestamp { centis } => {
-					let current_timestamp = state
-						.song
-						.song
-						.lyrics
-						.time_at_cursor(state.cursor.pos().x, state.cursor.pos().y)
-						.ok_or_eyre("No timestamp at cursor")?
-						.time();
-					let timestamp = Duration::from_millis(max(
-						current_timestamp.as_millis() as i32 + centis * 10,
-						0,
-					) as u64);
-					state
-						.song
-						.set_timestamp(state.cursor.pos(), Some(timestamp))?;
+				.update_pos(state.song.song.lyrics.line_widths())
+				.update_scroll(
+					Position::new(
+						state
+							.song
+							.song
+							.lyrics
+							.line_widths()
+							.max()
+							.unwrap_or_default(),
+						state.song.song.lyrics.line_count(),
+					),
+					state.config.settings.scrolloff,
+				);
+				state.cursor.set_y(state.cursor.pos().y);
+			}
+			Action::AdjustTimestamp { centis } => {
+				let current_timestamp = state
+					.song
+					.song
+					.lyrics
+					.time_at_cursor(state.cursor.pos().x, state.cursor.pos().y)
+					.ok_or_eyre("No timestamp at cursor")?
+					.time();
+				let timestamp = Duration::from_millis(max(
+					current_timestamp.as_millis() as i32 + centis * 10,
+					0,
+				) as u64);
+				state
+					.song
+					.set_timestamp_at_cursor(state.cursor.pos(), Some(timestamp))?;
+			}
+			Action::AdjustOffset { millis } => {
+				let offset = state.song.song.lyrics.offset() + millis as i64;
+				state.song.set_offset((offset != 0).then_some(offset))?;
+			}
+			Action::BakeOffset => {
+				state.song.bake_offset()?;
+			}
+			Action::ExpandRepeatedLines => {
+				state.song.expand_repeats()?;
+			}
+			Action::CompressRepeatedLines => {
+				state.song.compress_repeats()?;
+			}
+			Action::ExportLyrics { format } => {
+				let path = state.song.export_path(format);
+				if path != state.song.song.lrc_file && path.exists() {
+					state.active_modal = Some(Modal::ConfirmOverwrite { format });
+				} else {
+					state.export_lyrics(format)?;
+				}
+			}
+			Action::ImportLyrics { format } => {
+				state.song.import_lyrics(format)?;
+			}
+			Action::SetVoice { voice } => {
+				state.song.set_voice(state.cursor.pos().y, voice)?;
+			}
+			Action::CycleVoice => {
+				state.song.cycle_voice(state.cursor.pos().y)?;
+			}
+			Action::ViewProblems => {
+				state.modal.problems = lint::check(&state.song.song.lyrics, &state.track_info());
+				state.modal.problems_selected = 0;
+				if let Some(problem) = state.modal.problems.first() {
+					state.jump_cursor(Position::new(problem.x, problem.line));
 				}
-				Action::OpenInEditor => {
-					state.open_in_editor()?;
+				state.active_modal = Some(Modal::Problems);
+			}
+			Action::ViewHistory => {
+				HistoryModal::select_current(state);
+				state.modal.popup_scroll = 0;
+				state.active_modal = Some(Modal::History);
+			}
+			Action::FixSafeProblems => {
+				let track = state.track_info();
+				state.song.fix_safe_problems(&track)?;
+			}
+			Action::OpenInEditor => {
+				state.open_in_editor()?;
+			}
+			Action::EnterInsertMode => {
+				self.enter_insert_mode(state);
+			}
+			Action::OpenLine { below } => {
+				let y = state.song.open_line(state.cursor.pos().y, below)?;
+				self.enter_insert_mode(state);
+				state.jump_cursor(Position::new(0, y));
+			}
+			Action::DeleteLine => {
+				state.song.delete_line(state.cursor.pos().y)?;
+				state.jump_cursor(state.cursor.pos());
+			}
+			Action::SplitLine { timestamp } => {
+				let pos = state.cursor.pos();
+				let at = state.song.song.lyrics.lines()[pos.y as usize].byte_index_at(pos.x);
+				let playback_time = get_sync_time(state).ok();
+				state.song.split_line(pos.y, at, timestamp, playback_time)?;
+				state.jump_cursor(Position::new(0, pos.y + 1));
+			}
+			Action::JoinLines => {
+				let y = state.cursor.pos().y;
+				let at = state.song.join_lines(y)?;
+				let x = state.song.song.lyrics.lines()[y as usize].column_at(at);
+				state.jump_cursor(Position::new(x, y));
+			}
+			Action::MoveLine { amount } => {
+				let pos = state.cursor.pos();
+				let y = state.song.move_line(pos.y, amount)?;
+				state.jump_cursor(Position::new(pos.x, y));
+			}
+			Action::EnterVisualMode => {
+				state.editor_mode = EditorMode::Visual {
+					anchor: state.cursor.pos().y,
+				};
+			}
+			Action::ClearTimestamps => {
+				let y = state.cursor.pos().y;
+				state.song.clear_timestamps(y..=y)?;
+			}
+			Action::CopyTimestamps => {
+				let y = state.cursor.pos().y as usize;
+				state.copied_lines = vec![state.song.song.lyrics.lines()[y].clone()];
+			}
+			Action::PasteTimestamps => {
+				state
+					.song
+					.paste_timestamps(state.cursor.pos().y, &state.copied_lines)?;
+			}
+			Action::SetStretchAnchor => {
+				let line = state.cursor.pos().y;
+				let from = state
+					.song
+					.song
+					.lyrics
+					.time_at_line(line)
+					.ok_or_eyre("Anchor line must be synced")?
+					.time();
+				let to = get_sync_time(state)?;
+				state.stretch_anchors.retain(|anchor| anchor.line != line);
+				state.stretch_anchors.push(StretchAnchor { line, from, to });
+				if state.stretch_anchors.len() > 2 {
+					state.stretch_anchors.remove(0);
 				}
-				Action::Cancel if state.file_browser.directory().exists() => {
-					if state.song.changed {
-						state.active_modal = Some(Modal::GoBack);
-					} else {
-						self.back_to_file_tree(state);
-					}
+			}
+			Action::StretchTimestamps => {
+				let last = state.song.song.lyrics.line_count() - 1;
+				stretch_timestamps(state, 0..=last)?;
+			}
+			Action::InterpolateTimestamps { interpolation } => {
+				let last = state.song.song.lyrics.line_count() - 1;
+				state.song.interpolate_timestamps(
+					0..=last,
+					interpolation,
+					state.config.settings.timestamp_precision,
+				)?;
+			}
+			Action::Cancel if state.file_browser.directory().exists() => {
+				if state.song.changed {
+					state.active_modal = Some(Modal::GoBack);
+				} else {
+					self.back_to_file_tree(state);
 				}
-				_ => return Ok(false),
 			}
-			Ok(true)
-		} else {
-			Ok(false)
+			_ => return Ok(false),
+		}
+		Ok(true)
+	}
+}
+
+impl InputHandler for EditorView {
+	type State = AppState;
+
+	fn handle_input(self, key_chord: KeyChord, state: &mut AppState) -> eyre::Result<bool> {
+		if let EditorMode::Insert { .. } = state.editor_mode {
+			return self.handle_insert_input(key_chord, state);
+		}
+
+		let Some(action) = state
+			.config
+			.keys
+			.get_action(key_chord, Context::Editor)
+			.or(state.config.keys.get_action(key_chord, Context::Global))
+		else {
+			return Ok(false);
+		};
+		if let Some(selection) = state.editor_mode.selection(state.cursor.pos().y)
+			&& self.handle_selection_action(action, selection, state)?
+		{
+			return Ok(true);
 		}
+		self.handle_action(action, state)
 	}
 }
 
+/// Replaces the bytes in `range` of the line at the cursor and moves the cursor after the new text
+fn replace_at_cursor(state: &mut AppState, range: Range<usize>, text: &str) -> eyre::Result<()> {
+	let EditorMode::Insert { edited_line } = state.editor_mode else {
+		return Ok(());
+	};
+	let y = state.cursor.pos().y;
+	state
+		.song
+		.replace_text(y, range.clone(), text, edited_line == Some(y))?;
+	state.editor_mode = EditorMode::Insert {
+		edited_line: Some(y),
+	};
+
+	let x = state.song.song.lyrics.lines()[y as usize].column_at(range.start + text.len());
+	state.jump_cursor(Position::new(x, y));
+	Ok(())
+}
+
+/// Deletes `amount` characters after the cursor, or before it if negative
+fn delete_at_cursor(state: &mut AppState, amount: i16) -> eyre::Result<()> {
+	let pos = state.cursor.pos();
+	let line = &state.song.song.lyrics.lines()[pos.y as usize];
+	let text = line.text();
+	let idx = line.byte_index_at(pos.x);
+	let range = if amount < 0 {
+		let start = text[..idx]
+			.char_indices()
+			.rev()
+			.nth(amount.unsigned_abs() as usize - 1)
+			.map_or(0, |(i, _)| i);
+		start..idx
+	} else {
+		let end = text[idx..]
+			.char_indices()
+			.nth(amount as usize)
+			.map_or(text.len(), |(i, _)| idx + i);
+		idx..end
+	};
+	if range.is_empty() {
+		return Ok(());
+	}
+	replace_at_cursor(state, range, "")
+}
+
+/// Time-stretches the lines in `range` to fit the two anchors, which are then used up
+fn stretch_timestamps(state: &mut AppState, range: RangeInclusive<u16>) -> eyre::Result<()> {
+	let [first, second] = state.stretch_anchors[..] else {
+		return Err(eyre::eyre!("Mark two anchor lines first"));
+	};
+	state
+		.song
+		.stretch_timestamps(range, TimeStretch::new(first, second)?)?;
+	state.stretch_anchors.clear();
+	Ok(())
+}
+
 fn get_player(state: &AppState) -> eyre::Result<&AudioPlayer> {
 	state
 		.audio
@@ -359,6 +700,20 @@ fn get_player(state: &AppState) -> eyre::Result<&AudioPlayer> {
 		.ok_or_eyre("No audio playing")
 }
 
+fn get_sync_time(state: &AppState) -> eyre::Result<Duration> {
+	let player = get_player(state)?;
+	let time = (TimeDelta::from_std(player.position()).unwrap_or_default()
+		+ state.config.settings.sync_offset)
+		.to_std()
+		.unwrap_or_default()
+		.min(player.duration());
+	Ok(state
+		.config
+		.settings
+		.timestamp_precision
+		.truncate(state.song.song.lyrics.remove_offset(time)))
+}
+
 impl StatefulWidget for EditorView {
 	type State = AppState;
 
@@ -53,7 +53,10 @@ impl FileTreeView {
 				state.audio.audio_player =
 					Some(state.audio.audio_device.try_play(song.mp3_file.clone())?);
 
-				state.song.load_from_song(song)?;
+				state
+					.song
+					.load_from_song(song, state.history_dir(), &state.config.settings)?;
+				state.report_diagnostics();
 				state.active_view = View::Editor;
 			}
 			FileBrowserItem::Directory(directory) => {
@@ -72,7 +75,7 @@ impl FileTreeView {
 		state
 			.file_browser
 			.cursor
-			.set_y((target as u16).max(0) as u16)
+			.set_y(target)
 			.update_pos(iter::repeat_n(1, available_lines))
 			.update_scroll(Position::new(0, lines), state.config.settings.scrolloff);
 	}
@@ -0,0 +1,104 @@
+use ratatui::{
+	layout::{Alignment, Constraint, Flex, Layout},
+	widgets::{Block, BorderType, Clear, Padding, StatefulWidget, Widget},
+};
+
+use crate::{
+	config::{Action, Context},
+	state::AppState,
+	tui::{input_handler::InputHandler, widgets::HistoryWidget},
+};
+
+pub struct HistoryModal;
+
+impl HistoryModal {
+	fn select(state: &mut AppState, selected: usize) {
+		state.modal.history_selected = selected.min(state.song.history.entries().len());
+	}
+
+	/// Selects the row of the state the lyrics are at, the first row is the state they were opened
+	/// in and the others are the edits in the order they were made
+	pub fn select_current(state: &mut AppState) {
+		let current = state.song.history.current().map_or(0, |entry| entry + 1);
+		Self::select(state, current);
+	}
+}
+
+impl InputHandler for HistoryModal {
+	type State = AppState;
+
+	fn handle_input(
+		self,
+		key_chord: crate::config::KeyChord,
+		state: &mut Self::State,
+	) -> color_eyre::eyre::Result<bool> {
+		let Some(action) = state
+			.config
+			.keys
+			.get_action(key_chord, Context::ScrollablePopup)
+			.or(state.config.keys.get_action(key_chord, Context::Global))
+			.or(state.config.keys.get_action(key_chord, Context::Editor))
+		else {
+			return Ok(false);
+		};
+
+		match action {
+			Action::MoveCursorY { amount } => {
+				let selected = state.modal.history_selected as isize + amount as isize;
+				Self::select(state, selected.max(0) as usize);
+			}
+			Action::SetCursorY { y } => Self::select(state, y as usize),
+			Action::Undo => {
+				state.song.undo()?;
+				Self::select_current(state);
+			}
+			Action::Redo => {
+				state.song.redo()?;
+				Self::select_current(state);
+			}
+			Action::Confirm => {
+				state
+					.song
+					.jump_to_history(state.modal.history_selected.checked_sub(1))?;
+				state.active_modal = None;
+			}
+			Action::Cancel | Action::ViewHistory => {
+				state.active_modal = None;
+			}
+			_ => return Ok(false),
+		};
+
+		Ok(true)
+	}
+}
+
+impl StatefulWidget for HistoryModal {
+	type State = AppState;
+
+	fn render(
+		self,
+		area: ratatui::prelude::Rect,
+		buf: &mut ratatui::prelude::Buffer,
+		state: &mut Self::State,
+	) {
+		let [area] = Layout::horizontal([Constraint::Max(100)])
+			.flex(Flex::Center)
+			.areas(area);
+		let [area] = Layout::vertical([Constraint::Percentage(50)])
+			.flex(Flex::End)
+			.areas(area);
+
+		Clear.render(area, buf);
+
+		let block = Block::bordered()
+			.padding(Padding::symmetric(1, 0))
+			.border_type(BorderType::Rounded)
+			.border_style(state.config.theme.border_info)
+			.title_alignment(Alignment::Center)
+			.title(format!("History ({})", state.song.history.entries().len()));
+		let inner = block.inner(area);
+		block.render(area, buf);
+
+		HistoryWidget.render(inner, buf, state);
+	}
+}
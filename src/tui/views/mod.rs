@@ -1,16 +1,26 @@
+use std::ops::RangeInclusive;
+
+use crate::lyrics::LyricsFormat;
+
 mod confirm_back_modal;
 mod confirm_modal;
+mod confirm_overwrite_modal;
 mod confirm_quit_modal;
 mod editor_view;
 mod file_tree_view;
+mod history_modal;
 mod keys_modal;
+mod problems_modal;
 mod toasts_overlay;
 
 pub use confirm_back_modal::ConfirmBackModal;
+pub use confirm_overwrite_modal::ConfirmOverwriteModal;
 pub use confirm_quit_modal::ConfirmQuitModal;
 pub use editor_view::EditorView;
 pub use file_tree_view::FileTreeView;
+pub use history_modal::HistoryModal;
 pub use keys_modal::KeysModal;
+pub use problems_modal::ProblemsModal;
 pub use toasts_overlay::ToastsOverlay;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -19,9 +29,40 @@ pub enum View {
 	Editor,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EditorMode {
+	#[default]
+	Normal,
+	/// typing into the lyrics, changes to `edited_line` are undone together until another line
+	/// is typed on or insert mode is left
+	Insert { edited_line: Option<u16> },
+	/// selecting the lines between `anchor` and the cursor
+	Visual { anchor: u16 },
+	/// syncing the lines `start..=end` again one after another, once `synced` each sync is undone
+	/// together with the ones before it
+	Resync { start: u16, end: u16, synced: bool },
+}
+
+impl EditorMode {
+	/// The lines selected with the cursor on line `cursor_y`
+	pub fn selection(self, cursor_y: u16) -> Option<RangeInclusive<u16>> {
+		match self {
+			Self::Visual { anchor } => Some(anchor.min(cursor_y)..=anchor.max(cursor_y)),
+			Self::Resync { start, end, .. } => Some(start..=end),
+			Self::Normal | Self::Insert { .. } => None,
+		}
+	}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Modal {
 	ConfirmQuit,
+	/// before exporting lyrics over an existing file
+	ConfirmOverwrite {
+		format: LyricsFormat,
+	},
 	GoBack,
+	History,
 	Keys,
+	Problems,
 }
@@ -0,0 +1,102 @@
+use ratatui::{
+	layout::{Alignment, Constraint, Flex, Layout, Position},
+	widgets::{Block, BorderType, Clear, Padding, StatefulWidget, Widget},
+};
+
+use crate::{
+	config::{Action, Context},
+	lyrics::lint,
+	state::AppState,
+	tui::{input_handler::InputHandler, widgets::ProblemsWidget},
+};
+
+pub struct ProblemsModal;
+
+impl ProblemsModal {
+	fn select(state: &mut AppState, selected: usize) {
+		let Some(last) = state.modal.problems.len().checked_sub(1) else {
+			return;
+		};
+		state.modal.problems_selected = selected.min(last);
+		let problem = &state.modal.problems[state.modal.problems_selected];
+		state.jump_cursor(Position::new(problem.x, problem.line));
+	}
+}
+
+impl InputHandler for ProblemsModal {
+	type State = AppState;
+
+	fn handle_input(
+		self,
+		key_chord: crate::config::KeyChord,
+		state: &mut Self::State,
+	) -> color_eyre::eyre::Result<bool> {
+		let Some(action) = state
+			.config
+			.keys
+			.get_action(key_chord, Context::ScrollablePopup)
+			.or(state.config.keys.get_action(key_chord, Context::Global))
+			.or(state.config.keys.get_action(key_chord, Context::Editor))
+		else {
+			return Ok(false);
+		};
+
+		match action {
+			Action::MoveCursorY { amount } => {
+				let selected = state.modal.problems_selected as isize + amount as isize;
+				Self::select(state, selected.max(0) as usize);
+			}
+			Action::SetCursorY { y } => Self::select(state, y as usize),
+			Action::FixSafeProblems => {
+				let track = state.track_info();
+				state.song.fix_safe_problems(&track)?;
+				state.modal.problems = lint::check(&state.song.song.lyrics, &track);
+				Self::select(state, state.modal.problems_selected);
+			}
+			Action::Confirm | Action::Cancel | Action::ViewProblems => {
+				state.active_modal = None;
+			}
+			_ => return Ok(false),
+		};
+
+		Ok(true)
+	}
+}
+
+impl StatefulWidget for ProblemsModal {
+	type State = AppState;
+
+	fn render(
+		self,
+		area: ratatui::prelude::Rect,
+		buf: &mut ratatui::prelude::Buffer,
+		state: &mut Self::State,
+	) {
+		let [area] = Layout::horizontal([Constraint::Max(100)])
+			.flex(Flex::Center)
+			.areas(area);
+		let [area] = Layout::vertical([Constraint::Percentage(50)])
+			.flex(Flex::End)
+			.areas(area);
+
+		Clear.render(area, buf);
+
+		let (border_style, title) = match state.modal.problems.len() {
+			0 => (state.config.theme.border_info, "No problems".to_owned()),
+			count => (
+				state.config.theme.border_warn,
+				format!("Problems ({count})"),
+			),
+		};
+		let block = Block::bordered()
+			.padding(Padding::symmetric(1, 0))
+			.border_type(BorderType::Rounded)
+			.border_style(border_style)
+			.title_alignment(Alignment::Center)
+			.title(title);
+		let inner = block.inner(area);
+		block.render(area, buf);
+
+		ProblemsWidget.render(inner, buf, state);
+	}
+}
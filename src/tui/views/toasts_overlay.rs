@@ -5,7 +5,10 @@ use ratatui::{
 	widgets::{Clear, Paragraph, StatefulWidget, Widget},
 };
 
-use crate::{state::AppState, tui::widgets::ToastWidget};
+use crate::{
+	state::{AppState, ToastKind},
+	tui::widgets::ToastWidget,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ToastsOverlay;
@@ -43,8 +46,12 @@ impl StatefulWidget for ToastsOverlay {
 		for (toast, &area) in state.toasts.iter().zip(areas.iter()) {
 			Clear.render(area, buf);
 
-			let toast = ToastWidget::new(Paragraph::new(toast.text.as_str()))
-				.border_style(state.config.theme.border_err);
+			let border_style = match toast.kind {
+				ToastKind::Error => state.config.theme.border_err,
+				ToastKind::Warning => state.config.theme.border_warn,
+			};
+			let toast =
+				ToastWidget::new(Paragraph::new(toast.text.as_str())).border_style(border_style);
 			toast.render(area, buf);
 		}
 	}
@@ -0,0 +1,100 @@
+use std::time::{Duration, SystemTime};
+
+use ratatui::{
+	layout::{Constraint, Layout, Rect},
+	style::Style,
+	text::{Line, Span},
+	widgets::{Block, StatefulWidget, Widget},
+};
+
+use crate::state::AppState;
+
+pub struct HistoryWidget;
+
+impl HistoryWidget {
+	fn format_age(age: Duration) -> String {
+		match age.as_secs() {
+			..60 => "just now".to_owned(),
+			secs @ ..3600 => format!("{} min ago", secs / 60),
+			secs @ ..86400 => format!("{} h ago", secs / 3600),
+			secs => format!("{} days ago", secs / 86400),
+		}
+	}
+}
+
+impl StatefulWidget for HistoryWidget {
+	type State = AppState;
+
+	fn render(
+		self,
+		area: ratatui::prelude::Rect,
+		buf: &mut ratatui::prelude::Buffer,
+		state: &mut Self::State,
+	) {
+		let selected = state.modal.history_selected;
+		let height = area.height as usize;
+		if height == 0 {
+			return;
+		}
+		let scroll = &mut state.modal.popup_scroll;
+		*scroll = (*scroll as usize).clamp((selected + 1).saturating_sub(height), selected) as u16;
+
+		let history = &state.song.history;
+		let theme = &state.config.theme;
+		let num_width = history.entries().len().to_string().len() as u16;
+		let layout = Layout::horizontal([
+			Constraint::Length(1),
+			Constraint::Length(num_width),
+			Constraint::Fill(1),
+			Constraint::Length(12),
+		])
+		.spacing(1);
+
+		// the first row is the state the lyrics were opened in, before any edit
+		let rows = std::iter::once(None)
+			.chain((0..history.entries().len()).map(Some))
+			.enumerate()
+			.skip(*scroll as usize)
+			.take(height);
+		let now = SystemTime::now();
+		for ((i, entry), y) in rows.zip(area.y..) {
+			let row = Rect::new(area.x, y, area.width, 1);
+			if i == selected {
+				Block::new().style(theme.cursorline).render(row, buf);
+			}
+			let [current_area, num_area, description_area, age_area] = layout.areas(row);
+
+			if entry == history.current() {
+				Span::styled("●", theme.accent).render(current_area, buf);
+			}
+			// edits which were undone and then branched off from are greyed out
+			let style = if history.is_reached(entry) {
+				Style::default()
+			} else {
+				theme.text_secondary
+			};
+			let Some(idx) = entry else {
+				Span::styled("as opened", style).render(description_area, buf);
+				continue;
+			};
+			let entry = &history.entries()[idx];
+
+			Line::from(Span::styled((idx + 1).to_string(), theme.accent))
+				.right_aligned()
+				.render(num_area, buf);
+			let mut description = vec![Span::styled(entry.description(), style)];
+			if entry.parent() != idx.checked_sub(1) {
+				let branch = match entry.parent() {
+					Some(parent) => format!("  after {}", parent + 1),
+					None => "  after opening".to_owned(),
+				};
+				description.push(Span::styled(branch, theme.text_secondary));
+			}
+			Line::from(description).render(description_area, buf);
+			let age = now.duration_since(entry.time()).unwrap_or_default();
+			Line::from(Span::styled(Self::format_age(age), theme.text_secondary))
+				.right_aligned()
+				.render(age_area, buf);
+		}
+	}
+}
@@ -105,8 +105,7 @@ impl StatefulWidget for KeysWidget {
 			match line {
 				Line::Blank => (),
 				Line::ContextHeader(context) => {
-					let s: &str = context.into();
-					Span::from(s)
+					Span::from(context)
 						.style(state.config.theme.title)
 						.render(area, buf);
 				}
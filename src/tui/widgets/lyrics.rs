@@ -1,63 +1,102 @@
-use std::{cmp, iter};
-
 use ratatui::{
 	buffer::Buffer,
 	layout::{Constraint, Layout, Position, Rect},
 	style::Style,
 	symbols,
-	text::Span,
+	text::{Line, Span},
 	widgets::{Block, StatefulWidget, Widget},
 };
 use unicode_width::UnicodeWidthChar;
 
-use crate::{lyrics::TimeIndexEntry, state::AppState};
+use crate::{
+	lyrics::{LyricLine, TimeIndexEntry},
+	state::AppState,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LyricsWidget;
 
+impl LyricsWidget {
+	/// Rows taken up by a line, one for the line itself and one for each translation
+	fn line_height(line: &LyricLine) -> u16 {
+		1 + line.secondary_lines().len() as u16
+	}
+
+	/// Display column of the first character at or after `scroll_x` and its byte index
+	fn scrolled_start(text: &str, scroll_x: u16) -> (u16, usize) {
+		text.char_indices()
+			.scan(0, |pos, (idx, c)| {
+				let current_pos = *pos;
+				*pos += c.width().unwrap_or_default() as u16;
+				Some((current_pos, idx))
+			})
+			.find(|(pos, _)| *pos >= scroll_x)
+			.unwrap_or((scroll_x, text.len()))
+	}
+}
+
 impl StatefulWidget for LyricsWidget {
 	type State = AppState;
 
 	fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-		let scroll_y = state
-			.cursor
-			.scroll()
-			.y
-			.min(state.song.song.lyrics.line_count().max(1) - 1);
-		let rows = cmp::min(area.height, state.song.song.lyrics.line_count() - scroll_y) as usize;
-
-		let layout = Layout::vertical(
-			iter::repeat_n(Constraint::Length(1), rows).chain(iter::once(Constraint::Fill(1))),
-		);
-
-		let areas = layout.split(area);
-		let (_fill, line_areas) = areas.split_last().unwrap();
+		let lines = state.song.song.lyrics.lines();
+		let cursor_y = state.cursor.pos().y as usize;
+		let mut scroll_y = (state.cursor.scroll().y as usize).min(lines.len().max(1) - 1);
+		// the cursor scrolls by lines, lines with translations can push it below the screen
+		while scroll_y < cursor_y
+			&& lines[scroll_y..=cursor_y.min(lines.len() - 1)]
+				.iter()
+				.map(Self::line_height)
+				.sum::<u16>()
+				> area.height
+		{
+			scroll_y += 1;
+		}
 
-		let lyrics_lines = state
-			.song
-			.song
-			.lyrics
-			.lines()
+		let mut row = 0;
+		let visible_lines: Vec<_> = lines
 			.iter()
 			.enumerate()
-			.skip(scroll_y as usize)
-			.take(rows);
+			.skip(scroll_y)
+			.map_while(|(line_num, line)| {
+				let line_row = row;
+				row += Self::line_height(line);
+				(line_row < area.height).then_some((line_num, line, area.y + line_row))
+			})
+			.collect();
+		let row_area = |y: u16| Rect::new(area.x, y, area.width, 1);
 
 		let line_layout = Layout::horizontal([
-			Constraint::Length(8),
+			Constraint::Length(state.song.song.lyrics.timestamp_width()),
 			Constraint::Length(1),
 			Constraint::Length(2),
 			Constraint::Fill(1),
 		]);
 
-		if let Some(&line_area) = line_areas.get((state.cursor.pos().y - scroll_y) as usize) {
+		let cursor_row = visible_lines
+			.iter()
+			.find(|(line_num, _, _)| *line_num == cursor_y)
+			.map(|(_, _, y)| *y);
+		if let Some(y) = cursor_row {
 			Block::new()
 				.style(state.config.theme.cursorline)
-				.render(line_area, buf);
+				.render(row_area(y), buf);
+		}
+		if let Some(selection) = state.editor_mode.selection(cursor_y as u16) {
+			for (_, _, y) in visible_lines
+				.iter()
+				.filter(|(line_num, _, _)| selection.contains(&(*line_num as u16)))
+			{
+				Block::new()
+					.style(state.config.theme.selection)
+					.render(row_area(*y), buf);
+			}
 		}
 
 		let [_, _, _, text_area] = line_layout.areas(area);
-		state.cursor.set_render_origin(text_area.positions().next());
+		state
+			.cursor
+			.set_render_origin(cursor_row.map(|y| Position::new(text_area.x, y)));
 		state
 			.cursor
 			.set_screen_size(Position::new(text_area.width, text_area.height));
@@ -70,7 +109,8 @@ impl StatefulWidget for LyricsWidget {
 				.find_seq(player.position(), state.song.time_index_hint);
 		}
 
-		for ((line_num, lyric_line), &line_area) in lyrics_lines.zip(line_areas) {
+		let scroll_x = state.cursor.scroll().x;
+		for (line_num, lyric_line, y) in visible_lines {
 			let is_current_lyric = Some(line_num as u16) == current_lyric_line.line_num;
 			let lyrics_style = if is_current_lyric {
 				state.config.theme.lyrics_line
@@ -78,7 +118,7 @@ impl StatefulWidget for LyricsWidget {
 				Style::default()
 			};
 
-			let [time_area, border_area, mark_area, text_area] = line_layout.areas(line_area);
+			let [time_area, border_area, mark_area, text_area] = line_layout.areas(row_area(y));
 			Span::styled(
 				lyric_line.timestamp_text(),
 				state.config.theme.accent.patch(lyrics_style),
@@ -88,23 +128,52 @@ impl StatefulWidget for LyricsWidget {
 			if is_current_lyric {
 				Span::styled("𝅘𝅥𝅮", state.config.theme.accent.patch(lyrics_style))
 					.render(mark_area, buf);
+			} else if state
+				.stretch_anchors
+				.iter()
+				.any(|anchor| anchor.line as usize == line_num)
+			{
+				Span::styled("◆", state.config.theme.accent).render(mark_area, buf);
 			}
-			let (pos, idx) = lyric_line
-				.text()
-				.char_indices()
-				.scan(0, |pos, (idx, c)| {
-					let current_pos = *pos;
-					*pos += c.width().unwrap_or_default() as u16;
-					Some((current_pos, idx))
-				})
-				.find(|(pos, _)| *pos >= state.cursor.scroll().x)
-				.unwrap_or((state.cursor.scroll().x, lyric_line.text().len()));
-			let offset = pos - state.cursor.scroll().x;
+			let (pos, idx) = Self::scrolled_start(lyric_line.text(), scroll_x);
+			let offset = pos - scroll_x;
 			let [offset_area, text_area] =
 				Layout::horizontal([Constraint::Length(offset), Constraint::Fill(1)])
 					.areas(text_area);
 			Span::styled(">>>>", state.config.theme.text_secondary).render(offset_area, buf);
-			Span::styled(&lyric_line.text()[idx..], lyrics_style).render(text_area, buf);
+			let repeats = lyric_line
+				.repeat_timestamps()
+				.iter()
+				.map(|timestamp| format!(" [{}]", timestamp.text()))
+				.collect::<String>();
+			Line::from(vec![
+				Span::styled(
+					&lyric_line.text()[idx..],
+					state
+						.config
+						.theme
+						.voice(lyric_line.voice())
+						.patch(lyrics_style),
+				),
+				Span::styled(repeats, state.config.theme.text_secondary),
+			])
+			.render(text_area, buf);
+
+			for ((_, secondary), y) in lyric_line
+				.secondary_lines()
+				.iter()
+				.zip(y + 1..area.bottom())
+			{
+				let [_, border_area, _, text_area] = line_layout.areas(row_area(y));
+				Span::styled(symbols::line::THICK_VERTICAL, Style::default())
+					.render(border_area, buf);
+				let (pos, idx) = Self::scrolled_start(secondary.text(), scroll_x);
+				let [_, text_area] =
+					Layout::horizontal([Constraint::Length(pos - scroll_x), Constraint::Fill(1)])
+						.areas(text_area);
+				Span::styled(&secondary.text()[idx..], state.config.theme.text_secondary)
+					.render(text_area, buf);
+			}
 		}
 	}
 }
@@ -27,6 +27,7 @@ impl Widget for LyricsPreviewWidget<'_, '_> {
 				line,
 				timestamp_style: self.config.theme.accent,
 				show_timestamps: self.lyrics.sync_percentage() > 0,
+				timestamp_width: self.lyrics.timestamp_width(),
 			};
 
 			lyric_line_preview.render(row, buf);
@@ -38,12 +39,16 @@ struct LyricLinePreviewWidget<'a> {
 	line: &'a LyricLine,
 	timestamp_style: Style,
 	show_timestamps: bool,
+	timestamp_width: u16,
 }
 
 impl Widget for LyricLinePreviewWidget<'_> {
 	fn render(self, area: Rect, buf: &mut Buffer) {
 		if self.show_timestamps {
-			let constraints = [Constraint::Length(8), Constraint::Fill(1)];
+			let constraints = [
+				Constraint::Length(self.timestamp_width),
+				Constraint::Fill(1),
+			];
 			let [time_area, text_area] = Layout::horizontal(constraints).spacing(1).areas(area);
 
 			Text::styled(self.line.timestamp_text(), self.timestamp_style).render(time_area, buf);
@@ -1,14 +1,18 @@
 mod confirm_box;
+mod history;
 mod keys;
 mod lyrics;
 mod lyrics_preview;
 mod playback;
+mod problems;
 mod toast;
 mod volume;
 
 pub use confirm_box::ConfirmBox;
+pub use history::HistoryWidget;
 pub use keys::KeysWidget;
 pub use lyrics::LyricsWidget;
 pub use lyrics_preview::LyricsPreviewWidget;
 pub use playback::PlaybackWidget;
+pub use problems::ProblemsWidget;
 pub use toast::ToastWidget;
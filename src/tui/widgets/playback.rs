@@ -6,7 +6,7 @@ use ratatui::{
 	widgets::{Block, Borders, LineGauge, Padding, StatefulWidget, Widget},
 };
 
-use crate::state::AppState;
+use crate::{state::AppState, tui::EditorMode};
 
 use super::volume::VolumeWidget;
 
@@ -34,13 +34,37 @@ impl StatefulWidget for PlaybackWidget {
 		block.render(area, buf);
 
 		if let Some(ref player) = state.audio.audio_player {
-			let [_info_area, progress_area, controls_area] = Layout::vertical([
+			let [info_area, progress_area, controls_area] = Layout::vertical([
 				Constraint::Length(1),
 				Constraint::Length(1),
 				Constraint::Length(1),
 			])
 			.areas(inner);
 
+			let mode = match state.editor_mode {
+				EditorMode::Normal => None,
+				EditorMode::Insert { .. } => Some("-- INSERT --"),
+				EditorMode::Visual { .. } => Some("-- VISUAL --"),
+				EditorMode::Resync { .. } => Some("-- RESYNC --"),
+			};
+			if let Some(mode) = mode {
+				Line::from(mode)
+					.style(state.config.theme.accent.bold())
+					.render(info_area, buf);
+			} else if state.song.song.has_file {
+				Line::from(state.song.song.encoding.to_string())
+					.style(state.config.theme.text_secondary)
+					.render(info_area, buf);
+			}
+
+			let offset = state.song.song.lyrics.offset();
+			if offset != 0 {
+				Line::from(format!("offset {offset:+}ms"))
+					.style(state.config.theme.text_secondary)
+					.right_aligned()
+					.render(info_area, buf);
+			}
+
 			{
 				let [button_area, bar_area, label_area] = Layout::horizontal([
 					Constraint::Length(1),
@@ -0,0 +1,69 @@
+use ratatui::{
+	layout::{Constraint, Layout, Rect},
+	text::{Line, Span},
+	widgets::{Block, StatefulWidget, Widget},
+};
+
+use crate::state::AppState;
+
+pub struct ProblemsWidget;
+
+impl StatefulWidget for ProblemsWidget {
+	type State = AppState;
+
+	fn render(
+		self,
+		area: ratatui::prelude::Rect,
+		buf: &mut ratatui::prelude::Buffer,
+		state: &mut Self::State,
+	) {
+		let selected = state.modal.problems_selected;
+		let height = area.height as usize;
+		if height == 0 {
+			return;
+		}
+		let scroll = &mut state.modal.popup_scroll;
+		*scroll = (*scroll as usize).clamp((selected + 1).saturating_sub(height), selected) as u16;
+
+		let line_num_width = state
+			.modal
+			.problems
+			.iter()
+			.map(|problem| (problem.line + 1).to_string().len())
+			.max()
+			.unwrap_or_default() as u16;
+		let layout = Layout::horizontal([
+			Constraint::Length(line_num_width),
+			Constraint::Fill(1),
+			Constraint::Length(7),
+		])
+		.spacing(2);
+
+		let problems = state
+			.modal
+			.problems
+			.iter()
+			.enumerate()
+			.skip(*scroll as usize)
+			.take(height);
+		for ((i, problem), y) in problems.zip(area.y..) {
+			let row = Rect::new(area.x, y, area.width, 1);
+			if i == selected {
+				Block::new()
+					.style(state.config.theme.cursorline)
+					.render(row, buf);
+			}
+			let [line_num_area, message_area, fix_area] = layout.areas(row);
+			Line::from(Span::styled(
+				(problem.line + 1).to_string(),
+				state.config.theme.accent,
+			))
+			.right_aligned()
+			.render(line_num_area, buf);
+			Span::raw(problem.to_string()).render(message_area, buf);
+			if problem.is_safe_to_fix() {
+				Span::styled("fixable", state.config.theme.text_secondary).render(fix_area, buf);
+			}
+		}
+	}
+}